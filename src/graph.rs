@@ -1,69 +1,1016 @@
 use std::collections::HashMap;
 use std::hash::Hash;
-use std::cmp::Eq;
+use std::cmp::{Eq, Ordering};
+use std::error::Error;
+use std::fmt;
 
-pub struct Graph<T: Hash+Eq, W: Ord+Copy> {
-    nodes: HashMap<T, Node<T>>,
-    edges: HashMap<T, Vec<Edge<T, W>>>
+mod access;
+mod all_pairs;
+mod astar;
+mod biconnected;
+mod bipartite;
+mod centrality;
+mod coloring;
+mod bellman_ford;
+mod components;
+mod csr;
+mod dijkstra;
+mod dot;
+mod euler;
+mod generators;
+mod hamiltonian;
+mod io;
+mod k_shortest;
+mod max_flow;
+mod min_cost_flow;
+mod spanning_tree;
+mod topological;
+mod traversal;
+
+pub use access::{GraphAccess, IndexedEdges};
+pub use all_pairs::AllPairs;
+pub use astar::PathSearch;
+pub use bipartite::{Bipartition, Matching, OddCycle, Side};
+pub use centrality::{PageRank, PageRankConfig};
+pub use coloring::{ColorOrder, Coloring, ColoringError};
+pub use bellman_ford::{NegativeCycle, ShortestPathError};
+pub use components::Components;
+pub use csr::{CsrEdges, CsrGraph};
+pub use dijkstra::ShortestPaths;
+pub use dot::{DotError, Highlight};
+pub use euler::EulerError;
+pub use hamiltonian::TooManyNodes;
+pub use io::ReadError;
+pub use max_flow::MaxFlow;
+pub use min_cost_flow::{FlowEdge, MinCostFlow};
+pub use spanning_tree::SpanningForest;
+pub use topological::Cycle;
+pub use traversal::{Bfs, Dfs, DfsOrder};
+
+/* Weighted graph keyed by node id, carrying a payload of type N on every node
+and a payload of type E on every edge.
+
+Edges live in one slot vector and every node keeps the slots of the edges
+leaving it. Undirected edges are listed by both of their ends (a self loop
+only once), so anything walking out from a node sees undirected edges from
+either side without special casing, while the edge and its payload are only
+stored once.
+
+Nodes also get a dense index, kept until the node is removed and then handed
+to the next node added, and edges remember the indices of their ends. That
+lets GraphAccess walks keep their bookkeeping in vectors instead of hashing
+every node id they meet. */
+pub struct Graph<T: Hash+Eq, W: Ord+Copy, N = (), E = ()> {
+    nodes: HashMap<T, Node<T, N>>,
+    // node ids by index, None where a removed node's index is waiting to be reused
+    node_slots: Vec<Option<T>>,
+    free_node_slots: Vec<usize>,
+    edges: Vec<Option<Edge<T, W, E>>>,
+    free_slots: Vec<usize>,
+    direction: Direction,
+    duplicates: Duplicates<W>,
+    reverse_index: bool
+}
+
+/// Whether an edge only goes from its source to its target or goes both ways
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Directed,
+    Undirected
+}
+
+/// What add_edge does when there is already an edge between the two nodes
+#[derive(Clone, Copy, Debug)]
+pub enum Duplicates<W> {
+    /// keep every edge, making the graph a multigraph
+    Allow,
+    /// refuse the new edge with GraphError::DuplicateEdge, keeping the graph simple
+    Reject,
+    /// keep the graph simple by setting the existing edge's weight to merge(old, new)
+    Merge(fn(W, W) -> W)
+}
+
+/// Errors returned by operations that name nodes or edges which aren't in the graph
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphError<T> {
+    /// the node isn't in the graph
+    NodeNotFound(T),
+    /// the from end of an edge isn't in the graph
+    MissingSource(T),
+    /// the to end of an edge isn't in the graph
+    MissingTarget(T),
+    /// neither end of an edge is in the graph, (from, to)
+    MissingEndpoints(T, T),
+    /// both nodes exist but there is no edge between them, (from, to)
+    EdgeNotFound(T, T),
+    /// the graph doesn't allow parallel edges and this edge already exists, (from, to)
+    DuplicateEdge(T, T),
+    /// the operation doesn't support graphs with this direction
+    UnsupportedDirection(Direction)
+}
+
+impl<T: fmt::Debug> fmt::Display for GraphError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::NodeNotFound(id) => write!(f, "node {:?} is not in the graph", id),
+            GraphError::MissingSource(id) => write!(f, "edge source {:?} is not in the graph", id),
+            GraphError::MissingTarget(id) => write!(f, "edge target {:?} is not in the graph", id),
+            GraphError::MissingEndpoints(from, to) => write!(f, "neither edge source {:?} nor target {:?} is in the graph", from, to),
+            GraphError::EdgeNotFound(from, to) => write!(f, "there is no edge from {:?} to {:?}", from, to),
+            GraphError::DuplicateEdge(from, to) => write!(f, "there is already an edge from {:?} to {:?}", from, to),
+            GraphError::UnsupportedDirection(direction) => write!(f, "operation is not supported on {:?} graphs", direction)
+        }
+    }
+}
+
+impl<T: fmt::Debug> Error for GraphError<T> {}
+
+pub struct Node<T, N> {
+    id: T,
+    index: usize,
+    data: N,
+    // slots of the edges leaving this node
+    edges: Vec<usize>,
+    // slots of the edges coming in, only kept for directed graphs with the reverse index on
+    incoming: Vec<usize>
 }
 
-pub struct Node<T> {
-    id: T
+pub struct Edge<T, W: Ord+Copy, E> {
+    node_from: T,
+    node_to: T,
+    // node indices of the two ends
+    index_from: usize,
+    index_to: usize,
+    weight: W,
+    data: E
 }
 
-pub struct Edge<T, W: Ord+Copy> {
+/// Borrowed view of an edge, seen from the node it is being walked out of
+pub struct EdgeRef<'a, T, W, E> {
+    node_from: T,
+    node_to: T,
     weight: W,
-    node_to: T
+    data: &'a E
+}
+
+impl<'a, T: Copy, W: Copy, E> EdgeRef<'a, T, W, E> {
+    /// the node the edge is being walked out of
+    pub fn from(&self) -> T {
+        self.node_from
+    }
+
+    /// the node at the other end of the edge
+    pub fn to(&self) -> T {
+        self.node_to
+    }
+
+    pub fn weight(&self) -> W {
+        self.weight
+    }
+
+    pub fn data(&self) -> &'a E {
+        self.data
+    }
+}
+
+/// Iterator over the edges leaving a node
+pub struct EdgesFrom<'a, T: Hash+Eq, W: Ord+Copy, N, E> {
+    graph: &'a Graph<T, W, N, E>,
+    from: T,
+    slots: std::slice::Iter<'a, usize>
+}
+
+impl<'a, T: Hash+Eq+Copy, W: Ord+Copy, N, E> Iterator for EdgesFrom<'a, T, W, N, E> {
+    type Item = EdgeRef<'a, T, W, E>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let slot = *self.slots.next()?;
+        Some(self.graph.edge_at(self.from, slot))
+    }
+}
+
+/// Iterator over the nodes a node has edges to
+pub struct Neighbors<'a, T: Hash+Eq, W: Ord+Copy, N, E> {
+    edges: EdgesFrom<'a, T, W, N, E>
+}
+
+impl<'a, T: Hash+Eq+Copy, W: Ord+Copy, N, E> Iterator for Neighbors<'a, T, W, N, E> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.edges.next().map(|edge| edge.node_to)
+    }
+}
+
+/// Iterator over the edges coming into a node
+pub struct InEdges<'a, T: Hash+Eq, W: Ord+Copy, N, E> {
+    graph: &'a Graph<T, W, N, E>,
+    to: T,
+    slots: InSlots<'a>
+}
+
+// where incoming edges are found, a list of slots or a scan over every slot
+enum InSlots<'a> {
+    Listed(std::slice::Iter<'a, usize>),
+    Scan(std::ops::Range<usize>)
+}
+
+impl<'a, T: Hash+Eq+Copy, W: Ord+Copy, N, E> Iterator for InEdges<'a, T, W, N, E> {
+    type Item = EdgeRef<'a, T, W, E>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let slot = match &mut self.slots {
+                InSlots::Listed(slots) => return slots.next().map(|&slot| self.graph.edge_into(self.to, slot)),
+                InSlots::Scan(slots) => slots.next()?
+            };
+            if let Some(edge) = &self.graph.edges[slot] {
+                if edge.node_to == self.to {
+                    return Some(self.graph.edge_into(self.to, slot));
+                }
+            }
+        }
+    }
 }
 
 impl<T: Hash+Eq+Copy, W: Ord+Copy> Graph<T, W> {
+    /// Constructs a new empty directed multigraph
     pub fn new() -> Self {
+        Self::with_mode(Direction::Directed, Duplicates::Allow)
+    }
+
+    /// Constructs a new empty undirected multigraph
+    pub fn new_undirected() -> Self {
+        Self::with_mode(Direction::Undirected, Duplicates::Allow)
+    }
+
+    /// Constructs a new empty graph with the given direction and duplicate edge policy
+    pub fn with_mode(direction: Direction, duplicates: Duplicates<W>) -> Self {
+        Self::with_payloads(direction, duplicates)
+    }
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy, N, E> Graph<T, W, N, E> {
+    /// Constructs a new empty graph carrying node and edge payloads, with the given
+    /// direction and duplicate edge policy. The payload types come from the annotation,
+    /// e.g. Graph::<u32, u32, City, Road>::with_payloads(..)
+    pub fn with_payloads(direction: Direction, duplicates: Duplicates<W>) -> Self {
         Graph {
             nodes: HashMap::new(),
-            edges: HashMap::new(),
+            node_slots: Vec::new(),
+            free_node_slots: Vec::new(),
+            edges: Vec::new(),
+            free_slots: Vec::new(),
+            direction,
+            duplicates,
+            reverse_index: false
         }
     }
 
-    pub fn add_node(&mut self, id: T) {
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.direction == Direction::Directed
+    }
+
+    /// checks to see if the graph allows parallel edges
+    pub fn is_multigraph(&self) -> bool {
+        matches!(self.duplicates, Duplicates::Allow)
+    }
+
+    /// Adds a node with a default payload, does nothing if the node is already there
+    pub fn add_node(&mut self, id: T) where N: Default {
         if !self.nodes.contains_key(&id) {
-            self.nodes.insert(id, Node::new(id));
+            self.insert_node(id, N::default());
+        }
+    }
+
+    /// Adds a node with the given payload, replacing the payload if the node is already there
+    pub fn add_node_with(&mut self, id: T, data: N) {
+        match self.nodes.get_mut(&id) {
+            Some(node) => node.data = data,
+            None => self.insert_node(id, data)
         }
     }
 
-    pub fn add_edge(&mut self, id_from: T, id_to: T, weight: W) {
-        if self.nodes.contains_key(&id_to) && self.nodes.contains_key(&id_from) {
-            if !self.edges.contains_key(&id_from) {
-                self.edges.insert(id_from, vec![Edge::new(id_to, weight)]);
-            } else {
-                let edge_vector = self.edges.get_mut(&id_from).unwrap();
-                edge_vector.push(Edge::new(id_to, weight));
+    /// returns the payload of a node
+    /// returns None if the node isn't in the graph
+    pub fn node_data(&self, id: T) -> Option<&N> {
+        self.nodes.get(&id).map(|node| &node.data)
+    }
+
+    /// returns the payload of a node for updating
+    /// returns None if the node isn't in the graph
+    pub fn node_data_mut(&mut self, id: T) -> Option<&mut N> {
+        self.nodes.get_mut(&id).map(|node| &mut node.data)
+    }
+
+    /// Adds a weighted edge with a default payload from id_from to id_to, see add_edge_with
+    pub fn add_edge(&mut self, id_from: T, id_to: T, weight: W) -> Result<(), GraphError<T>> where E: Default {
+        self.add_edge_with(id_from, id_to, weight, E::default())
+    }
+
+    /// Adds a weighted edge with a payload from id_from to id_to, undirected graphs can walk it from either end
+    /// If the edge already exists the graph's Duplicates policy decides what happens, merging
+    /// keeps the payload of the existing edge
+    /// Returns an error naming the missing endpoint(s) if either node isn't in the graph
+    pub fn add_edge_with(&mut self, id_from: T, id_to: T, weight: W, data: E) -> Result<(), GraphError<T>> {
+        self.check_endpoints(id_from, id_to)?;
+        let existing = self.out_edges(id_from).find(|e| e.node_to == id_to).map(|e| e.weight);
+        match (existing, self.duplicates) {
+            (Some(_), Duplicates::Reject) => return Err(GraphError::DuplicateEdge(id_from, id_to)),
+            (Some(old), Duplicates::Merge(merge)) => return self.update_edge_weight(id_from, id_to, merge(old, weight)),
+            _ => {}
+        }
+        let edge = Edge::new(id_from, id_to, (self.nodes[&id_from].index, self.nodes[&id_to].index), weight, data);
+        let slot = match self.free_slots.pop() {
+            Some(slot) => {
+                self.edges[slot] = Some(edge);
+                slot
+            },
+            None => {
+                self.edges.push(Some(edge));
+                self.edges.len() - 1
             }
+        };
+        self.nodes.get_mut(&id_from).unwrap().edges.push(slot);
+        if self.mirrors(id_from, id_to) {
+            self.nodes.get_mut(&id_to).unwrap().edges.push(slot);
+        }
+        if self.keeps_incoming() {
+            self.nodes.get_mut(&id_to).unwrap().incoming.push(slot);
         }
+        Ok(())
     }
 
     pub fn get_edges_from(&self, id: T) -> Option<Vec<(T, W)>> {
-        let edges = self.edges.get(&id);
-        if let Some(vals) = edges {
-            Some(vals.iter().map(|e| (e.node_to, e.weight)).collect())
+        self.edges_from(id).map(|edges| edges.map(|e| (e.node_to, e.weight)).collect())
+    }
+
+    /// Borrows the edges leaving a node, undirected graphs include every edge touching it
+    /// Returns None if the node isn't in the graph
+    pub fn edges_from(&self, id: T) -> Option<EdgesFrom<'_, T, W, N, E>> {
+        self.nodes.get(&id).map(|node| EdgesFrom {
+            graph: self,
+            from: id,
+            slots: node.edges.iter()
+        })
+    }
+
+    /// Borrows the edges leaving a node, nothing if the node isn't in the graph
+    pub fn out_edges(&self, id: T) -> EdgesFrom<'_, T, W, N, E> {
+        EdgesFrom {
+            graph: self,
+            from: id,
+            slots: self.edge_slots(id).iter()
+        }
+    }
+
+    /// Walks the nodes that id has an edge to, once per edge
+    pub fn neighbors(&self, id: T) -> Neighbors<'_, T, W, N, E> {
+        Neighbors {
+            edges: self.out_edges(id)
+        }
+    }
+
+    /// Borrows the edges coming into a node, each seen from the node it leaves
+    /// Directed graphs without the reverse index have to scan every edge to find them
+    pub fn in_edges(&self, id: T) -> InEdges<'_, T, W, N, E> {
+        let slots = if self.direction == Direction::Undirected {
+            InSlots::Listed(self.edge_slots(id).iter())
+        } else if self.reverse_index {
+            match self.nodes.get(&id) {
+                Some(node) => InSlots::Listed(node.incoming.iter()),
+                None => InSlots::Listed([].iter())
+            }
         } else {
-            None
+            InSlots::Scan(0..self.edges.len())
+        };
+        InEdges {
+            graph: self,
+            to: id,
+            slots
+        }
+    }
+
+    /// counts the edges leaving a node, 0 if it isn't in the graph
+    pub fn out_degree(&self, id: T) -> usize {
+        self.edge_slots(id).len()
+    }
+
+    /// counts the edges coming into a node, 0 if it isn't in the graph
+    pub fn in_degree(&self, id: T) -> usize {
+        self.in_edges(id).count()
+    }
+
+    /// Keeps a list of incoming edges on every node so in_edges and in_degree take time in
+    /// the node's in degree rather than the size of the graph, at the cost of extra upkeep
+    /// whenever an edge is added or removed. Undirected graphs don't need it.
+    pub fn enable_reverse_index(&mut self) {
+        if self.reverse_index {
+            return;
+        }
+        self.reverse_index = true;
+        if self.keeps_incoming() {
+            for (slot, edge) in self.edges.iter().enumerate() {
+                if let Some(edge) = edge {
+                    self.nodes.get_mut(&edge.node_to).unwrap().incoming.push(slot);
+                }
+            }
+        }
+    }
+
+    /// Drops the incoming edge lists kept by enable_reverse_index
+    pub fn disable_reverse_index(&mut self) {
+        self.reverse_index = false;
+        for node in self.nodes.values_mut() {
+            node.incoming = Vec::new();
+        }
+    }
+
+    pub fn has_reverse_index(&self) -> bool {
+        self.reverse_index
+    }
+
+    /// Walks every node id, in no particular order
+    pub fn nodes(&self) -> impl Iterator<Item=T> + '_ {
+        self.nodes.keys().copied()
+    }
+
+    /// Walks every edge once, seen from the node it was added from, in no particular order
+    pub fn edges(&self) -> impl Iterator<Item=EdgeRef<'_, T, W, E>> + '_ {
+        self.edges.iter().flatten().map(|edge| EdgeRef {
+            node_from: edge.node_from,
+            node_to: edge.node_to,
+            weight: edge.weight,
+            data: &edge.data
+        })
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// counts the edges, undirected edges only once
+    pub fn edge_count(&self) -> usize {
+        self.edges.len() - self.free_slots.len()
+    }
+
+    /// returns the payload of the first edge from id_from to id_to for updating
+    /// returns None if there is no such edge
+    pub fn edge_data_mut(&mut self, id_from: T, id_to: T) -> Option<&mut E> {
+        let slot = self.find_slots(id_from, id_to).into_iter().next()?;
+        self.edges[slot].as_mut().map(|edge| &mut edge.data)
+    }
+
+    /// checks to see if id is a node in the graph
+    pub fn contains_node(&self, id: T) -> bool {
+        self.nodes.contains_key(&id)
+    }
+
+    /// checks to see if there is at least one edge from id_from to id_to
+    pub fn contains_edge(&self, id_from: T, id_to: T) -> bool {
+        self.out_edges(id_from).any(|e| e.node_to == id_to)
+    }
+
+    /// Removes a node along with every edge going into or out of it, returning its payload
+    /// Returns an error if the node isn't in the graph
+    pub fn remove_node(&mut self, id: T) -> Result<N, GraphError<T>> {
+        self.check_node(id)?;
+        let mut doomed: Vec<usize> = self.nodes[&id].edges.clone();
+        if self.keeps_incoming() {
+            doomed.extend(&self.nodes[&id].incoming);
+        } else if self.direction == Direction::Directed {
+            // edges coming in are only listed by the nodes they leave
+            for node in self.nodes.values() {
+                if node.id != id {
+                    doomed.extend(node.edges.iter().filter(|&&slot| self.edge_at(node.id, slot).node_to == id));
+                }
+            }
+        }
+        for slot in doomed {
+            self.unlink(slot);
+        }
+        let node = self.nodes.remove(&id).unwrap();
+        self.node_slots[node.index] = None;
+        self.free_node_slots.push(node.index);
+        Ok(node.data)
+    }
+
+    /// Removes every edge from id_from to id_to, returning how many were removed
+    /// Undirected graphs treat the edges back from id_to as the same edges
+    /// Returns an error if either node is missing or there is no such edge
+    pub fn remove_edge(&mut self, id_from: T, id_to: T) -> Result<usize, GraphError<T>> {
+        self.check_endpoints(id_from, id_to)?;
+        let slots = self.find_slots(id_from, id_to);
+        if slots.is_empty() {
+            return Err(GraphError::EdgeNotFound(id_from, id_to));
+        }
+        for &slot in &slots {
+            self.unlink(slot);
+        }
+        Ok(slots.len())
+    }
+
+    /// Sets the weight of every edge from id_from to id_to
+    /// Undirected graphs treat the edges back from id_to as the same edges
+    /// Returns an error if either node is missing or there is no such edge
+    pub fn update_edge_weight(&mut self, id_from: T, id_to: T, weight: W) -> Result<(), GraphError<T>> {
+        self.check_endpoints(id_from, id_to)?;
+        let slots = self.find_slots(id_from, id_to);
+        if slots.is_empty() {
+            return Err(GraphError::EdgeNotFound(id_from, id_to));
+        }
+        for slot in slots {
+            if let Some(edge) = self.edges[slot].as_mut() {
+                edge.weight = weight;
+            }
+        }
+        Ok(())
+    }
+
+    fn require_direction(&self, direction: Direction) -> Result<(), GraphError<T>> {
+        if self.direction == direction {
+            Ok(())
+        } else {
+            Err(GraphError::UnsupportedDirection(self.direction))
+        }
+    }
+
+    // gives every node a dense index, returning the ids in index order and the lookup from id to index
+    fn index_nodes(&self) -> (Vec<T>, HashMap<T, usize>) {
+        let ids: Vec<T> = self.nodes.keys().copied().collect();
+        let index = ids.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+        (ids, index)
+    }
+
+    // adds a node that isn't in the graph yet, reusing a freed index if there is one
+    fn insert_node(&mut self, id: T, data: N) {
+        let index = match self.free_node_slots.pop() {
+            Some(index) => {
+                self.node_slots[index] = Some(id);
+                index
+            },
+            None => {
+                self.node_slots.push(Some(id));
+                self.node_slots.len() - 1
+            }
+        };
+        self.nodes.insert(id, Node::new(id, index, data));
+    }
+
+    // every node's neighbors with edge direction ignored, directed edges are listed from both ends
+    fn undirected_neighbors(&self) -> HashMap<T, Vec<T>> {
+        let mut neighbors: HashMap<T, Vec<T>> = self.nodes.keys().map(|&id| (id, Vec::new())).collect();
+        for edge in self.oriented_edges() {
+            neighbors.get_mut(&edge.node_from).unwrap().push(edge.node_to);
+            if self.direction == Direction::Directed && edge.node_from != edge.node_to {
+                neighbors.get_mut(&edge.node_to).unwrap().push(edge.node_from);
+            }
+        }
+        neighbors
+    }
+
+    // every edge walked out of every node it leaves, so undirected edges show up from both ends
+    fn oriented_edges(&self) -> impl Iterator<Item=EdgeRef<'_, T, W, E>> + '_ {
+        self.nodes.values().flat_map(move |node| self.out_edges(node.id))
+    }
+
+    // slots of the edges leaving id, empty if id isn't in the graph
+    fn edge_slots(&self, id: T) -> &[usize] {
+        match self.nodes.get(&id) {
+            Some(node) => &node.edges,
+            None => &[]
+        }
+    }
+
+    // the edge in slot, seen from its end at from
+    fn edge_at(&self, from: T, slot: usize) -> EdgeRef<'_, T, W, E> {
+        let edge = self.edges[slot].as_ref().expect("Nodes only list slots of live edges");
+        EdgeRef {
+            node_from: from,
+            node_to: if edge.node_from == from { edge.node_to } else { edge.node_from },
+            weight: edge.weight,
+            data: &edge.data
+        }
+    }
+
+    // slots of every edge from id_from to id_to
+    fn find_slots(&self, id_from: T, id_to: T) -> Vec<usize> {
+        self.edge_slots(id_from).iter()
+            .copied()
+            .filter(|&slot| self.edge_at(id_from, slot).node_to == id_to)
+            .collect()
+    }
+
+    // the edge in slot, seen from the end it comes in at
+    fn edge_into(&self, to: T, slot: usize) -> EdgeRef<'_, T, W, E> {
+        let edge = self.edges[slot].as_ref().expect("Nodes only list slots of live edges");
+        EdgeRef {
+            node_from: if edge.node_to == to { edge.node_from } else { edge.node_to },
+            node_to: to,
+            weight: edge.weight,
+            data: &edge.data
+        }
+    }
+
+    // undirected graphs find incoming edges in the regular lists, so only directed graphs keep their own
+    fn keeps_incoming(&self) -> bool {
+        self.reverse_index && self.direction == Direction::Directed
+    }
+
+    // undirected edges are listed by both ends, except for self loops
+    fn mirrors(&self, id_from: T, id_to: T) -> bool {
+        self.direction == Direction::Undirected && id_from != id_to
+    }
+
+    // drops the edge in slot from the lists of both its ends and frees the slot
+    fn unlink(&mut self, slot: usize) -> Option<Edge<T, W, E>> {
+        let edge = self.edges[slot].take()?;
+        let mut ends = vec![edge.node_from];
+        if self.mirrors(edge.node_from, edge.node_to) {
+            ends.push(edge.node_to);
+        }
+        for end in ends {
+            if let Some(node) = self.nodes.get_mut(&end) {
+                if let Some(pos) = node.edges.iter().position(|&s| s == slot) {
+                    node.edges.remove(pos);
+                }
+            }
+        }
+        if self.keeps_incoming() {
+            if let Some(node) = self.nodes.get_mut(&edge.node_to) {
+                if let Some(pos) = node.incoming.iter().position(|&s| s == slot) {
+                    node.incoming.remove(pos);
+                }
+            }
+        }
+        self.free_slots.push(slot);
+        Some(edge)
+    }
+
+    fn check_node(&self, id: T) -> Result<(), GraphError<T>> {
+        if self.nodes.contains_key(&id) {
+            Ok(())
+        } else {
+            Err(GraphError::NodeNotFound(id))
+        }
+    }
+
+    fn check_endpoints(&self, id_from: T, id_to: T) -> Result<(), GraphError<T>> {
+        match (self.nodes.contains_key(&id_from), self.nodes.contains_key(&id_to)) {
+            (true, true) => Ok(()),
+            (false, true) => Err(GraphError::MissingSource(id_from)),
+            (true, false) => Err(GraphError::MissingTarget(id_to)),
+            (false, false) => Err(GraphError::MissingEndpoints(id_from, id_to))
         }
     }
 }
 
-impl<T> Node<T> {
-    pub fn new(id: T) -> Self {
+impl<T, N> Node<T, N> {
+    pub(crate) fn new(id: T, index: usize, data: N) -> Self {
         Node {
-            id
+            id,
+            index,
+            data,
+            edges: Vec::new(),
+            incoming: Vec::new()
         }
     }
 }
 
-impl<T, W: Ord+Copy> Edge<T, W> {
-    pub fn new(to: T, weight: W) -> Self {
+impl<T, W: Ord+Copy, E> Edge<T, W, E> {
+    pub(crate) fn new(from: T, to: T, (index_from, index_to): (usize, usize), weight: W, data: E) -> Self {
         Edge {
+            node_from: from,
+            node_to: to,
+            index_from,
+            index_to,
             weight,
-            node_to: to
+            data
         }
     }
 }
+
+/* Priority queue entry used by the search algorithms in this module.
+
+Ordering only looks at the priority so node ids don't need to be Ord, wrap
+in a Reverse before pushing onto a Heap to pop the smallest priority first. */
+struct Candidate<P, T> {
+    priority: P,
+    node: T
+}
+
+impl<P, T> Candidate<P, T> {
+    fn new(priority: P, node: T) -> Self {
+        Candidate {
+            priority,
+            node
+        }
+    }
+}
+
+impl<P: Ord, T> PartialEq for Candidate<P, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority
+    }
+}
+
+impl<P: Ord, T> Eq for Candidate<P, T> {}
+
+impl<P: Ord, T> PartialOrd for Candidate<P, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<P: Ord, T> Ord for Candidate<P, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority.cmp(&other.priority)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init() -> Graph<i32, i32> {
+        let mut graph = Graph::new();
+        for id in 1..=4 {
+            graph.add_node(id);
+        }
+        graph.add_edge(1, 2, 5).unwrap();
+        graph.add_edge(1, 3, 2).unwrap();
+        graph.add_edge(2, 3, 1).unwrap();
+        graph.add_edge(3, 1, 7).unwrap();
+        graph.add_edge(4, 3, 3).unwrap();
+        graph
+    }
+
+    #[test]
+    fn add_edge_missing_endpoints() {
+        let mut graph = init();
+        assert_eq!(graph.add_edge(9, 1, 1), Err(GraphError::MissingSource(9)));
+        assert_eq!(graph.add_edge(1, 9, 1), Err(GraphError::MissingTarget(9)));
+        assert_eq!(graph.add_edge(8, 9, 1), Err(GraphError::MissingEndpoints(8, 9)));
+        assert!(!graph.contains_edge(1, 9));
+    }
+
+    #[test]
+    fn contains_nodes_and_edges() {
+        let graph = init();
+        assert!(graph.contains_node(1));
+        assert!(!graph.contains_node(5));
+        assert!(graph.contains_edge(1, 2));
+        assert!(!graph.contains_edge(2, 1));
+        assert!(!graph.contains_edge(5, 1));
+    }
+
+    #[test]
+    fn remove_node_drops_its_edges() {
+        let mut graph = init();
+        graph.remove_node(3).unwrap();
+        assert!(!graph.contains_node(3));
+        assert!(!graph.contains_edge(1, 3));
+        assert!(!graph.contains_edge(3, 1));
+        assert_eq!(graph.get_edges_from(1), Some(vec![(2, 5)]));
+        assert_eq!(graph.get_edges_from(4), Some(vec![]));
+        assert_eq!(graph.remove_node(3), Err(GraphError::NodeNotFound(3)));
+    }
+
+    #[test]
+    fn remove_edge() {
+        let mut graph = init();
+        graph.add_edge(1, 2, 9).unwrap();
+        assert_eq!(graph.remove_edge(1, 2), Ok(2));
+        assert!(!graph.contains_edge(1, 2));
+        assert!(graph.contains_edge(1, 3));
+        assert_eq!(graph.remove_edge(1, 2), Err(GraphError::EdgeNotFound(1, 2)));
+        assert_eq!(graph.remove_edge(1, 9), Err(GraphError::MissingTarget(9)));
+    }
+
+    #[test]
+    fn update_edge_weight() {
+        let mut graph = init();
+        graph.update_edge_weight(1, 3, 10).unwrap();
+        assert_eq!(graph.get_edges_from(1), Some(vec![(2, 5), (3, 10)]));
+        assert_eq!(graph.update_edge_weight(2, 1, 1), Err(GraphError::EdgeNotFound(2, 1)));
+        assert_eq!(graph.update_edge_weight(0, 1, 1), Err(GraphError::MissingSource(0)));
+    }
+
+    #[test]
+    fn undirected_edges_go_both_ways() {
+        let mut graph = Graph::new_undirected();
+        for id in 1..=3 {
+            graph.add_node(id);
+        }
+        graph.add_edge(1, 2, 4).unwrap();
+        graph.add_edge(2, 2, 1).unwrap();
+        assert!(!graph.is_directed());
+        assert!(graph.contains_edge(2, 1));
+        assert_eq!(graph.get_edges_from(2), Some(vec![(1, 4), (2, 1)]));
+        graph.update_edge_weight(2, 1, 6).unwrap();
+        assert_eq!(graph.get_edges_from(1), Some(vec![(2, 6)]));
+        assert_eq!(graph.remove_edge(2, 1), Ok(1));
+        assert!(!graph.contains_edge(1, 2));
+        assert_eq!(graph.remove_edge(2, 2), Ok(1));
+        assert_eq!(graph.get_edges_from(2), Some(vec![]));
+    }
+
+    #[test]
+    fn undirected_remove_node() {
+        let mut graph = Graph::new_undirected();
+        for id in 1..=3 {
+            graph.add_node(id);
+        }
+        graph.add_edge(1, 2, 1).unwrap();
+        graph.add_edge(3, 2, 1).unwrap();
+        graph.remove_node(2).unwrap();
+        assert_eq!(graph.get_edges_from(1), Some(vec![]));
+        assert_eq!(graph.get_edges_from(3), Some(vec![]));
+    }
+
+    #[test]
+    fn multigraph_keeps_parallel_edges() {
+        let mut graph = init();
+        assert!(graph.is_multigraph());
+        graph.add_edge(1, 2, 1).unwrap();
+        assert_eq!(graph.get_edges_from(1), Some(vec![(2, 5), (3, 2), (2, 1)]));
+    }
+
+    #[test]
+    fn simple_graph_rejects_duplicates() {
+        let mut graph = Graph::with_mode(Direction::Undirected, Duplicates::Reject);
+        graph.add_node(1);
+        graph.add_node(2);
+        graph.add_edge(1, 2, 3).unwrap();
+        assert!(!graph.is_multigraph());
+        assert_eq!(graph.add_edge(1, 2, 4), Err(GraphError::DuplicateEdge(1, 2)));
+        assert_eq!(graph.add_edge(2, 1, 4), Err(GraphError::DuplicateEdge(2, 1)));
+        assert_eq!(graph.get_edges_from(1), Some(vec![(2, 3)]));
+    }
+
+    #[test]
+    fn simple_graph_merges_duplicates() {
+        let mut graph = Graph::with_mode(Direction::Undirected, Duplicates::Merge(std::cmp::min));
+        graph.add_node(1);
+        graph.add_node(2);
+        graph.add_edge(1, 2, 3).unwrap();
+        graph.add_edge(2, 1, 1).unwrap();
+        graph.add_edge(1, 2, 2).unwrap();
+        assert_eq!(graph.get_edges_from(1), Some(vec![(2, 1)]));
+        assert_eq!(graph.get_edges_from(2), Some(vec![(1, 1)]));
+
+        let mut graph = Graph::with_mode(Direction::Directed, Duplicates::Merge(|a, b| a + b));
+        graph.add_node(1);
+        graph.add_node(2);
+        graph.add_edge(1, 2, 3).unwrap();
+        graph.add_edge(1, 2, 4).unwrap();
+        graph.add_edge(2, 1, 1).unwrap();
+        assert_eq!(graph.get_edges_from(1), Some(vec![(2, 7)]));
+        assert_eq!(graph.get_edges_from(2), Some(vec![(1, 1)]));
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Road {
+        name: &'static str,
+        toll: u32
+    }
+
+    fn roads() -> Graph<char, u32, &'static str, Road> {
+        let mut graph = Graph::with_payloads(Direction::Undirected, Duplicates::Allow);
+        graph.add_node_with('a', "Aston");
+        graph.add_node_with('b', "Burford");
+        graph.add_node_with('c', "Cole");
+        graph.add_edge_with('a', 'b', 5, Road { name: "A40", toll: 2 }).unwrap();
+        graph.add_edge_with('b', 'c', 3, Road { name: "B4020", toll: 0 }).unwrap();
+        graph.add_edge_with('a', 'c', 9, Road { name: "A361", toll: 1 }).unwrap();
+        graph
+    }
+
+    #[test]
+    fn node_payloads() {
+        let mut graph = roads();
+        assert_eq!(graph.node_data('b'), Some(&"Burford"));
+        assert_eq!(graph.node_data('z'), None);
+        *graph.node_data_mut('b').unwrap() = "Burford Bridge";
+        assert_eq!(graph.node_data('b'), Some(&"Burford Bridge"));
+        graph.add_node_with('b', "Burford");
+        assert_eq!(graph.node_data('b'), Some(&"Burford"));
+        assert_eq!(graph.remove_node('c'), Ok("Cole"));
+        assert!(!graph.contains_edge('b', 'c'));
+    }
+
+    #[test]
+    fn edge_payloads() {
+        let mut graph = roads();
+        let from_b: Vec<(char, char, u32, &str)> = graph.edges_from('b').unwrap()
+            .map(|edge| (edge.from(), edge.to(), edge.weight(), edge.data().name))
+            .collect();
+        assert_eq!(from_b, vec![('b', 'a', 5, "A40"), ('b', 'c', 3, "B4020")]);
+        assert!(graph.edges_from('z').is_none());
+
+        // undirected edges are stored once, so either end sees the update
+        graph.edge_data_mut('c', 'b').unwrap().toll = 4;
+        assert_eq!(graph.edges_from('b').unwrap().nth(1).unwrap().data(), &Road { name: "B4020", toll: 4 });
+        assert!(graph.edge_data_mut('b', 'b').is_none());
+
+        let by_distance = graph.shortest_paths_from('a').unwrap();
+        assert_eq!(by_distance.path_to('c'), Some(vec!['a', 'b', 'c']));
+        let by_toll = graph.shortest_paths_by('a', |edge| edge.data().toll).unwrap();
+        assert_eq!(by_toll.path_to('c'), Some(vec!['a', 'c']));
+        assert_eq!(by_toll.distance_to('c'), Some(1));
+    }
+
+    #[test]
+    fn edge_slots_are_reused() {
+        let mut graph = roads();
+        graph.remove_edge('a', 'b').unwrap();
+        graph.add_edge_with('c', 'a', 1, Road { name: "C1", toll: 0 }).unwrap();
+        assert_eq!(graph.edges.len(), 3);
+        assert_eq!(graph.get_edges_from('a'), Some(vec![('c', 9), ('c', 1)]));
+        graph.remove_node('a').unwrap();
+        assert_eq!(graph.get_edges_from('c'), Some(vec![('b', 3)]));
+        assert_eq!(graph.free_slots.len(), 2);
+    }
+
+    fn incoming(graph: &Graph<i32, i32>, id: i32) -> Vec<(i32, i32)> {
+        let mut edges: Vec<(i32, i32)> = graph.in_edges(id).map(|edge| (edge.from(), edge.weight())).collect();
+        edges.sort();
+        edges
+    }
+
+    #[test]
+    fn neighbors_and_degrees() {
+        let graph = init();
+        let mut neighbors: Vec<i32> = graph.neighbors(1).collect();
+        neighbors.sort();
+        assert_eq!(neighbors, vec![2, 3]);
+        assert_eq!(graph.out_edges(1).map(|edge| edge.weight()).sum::<i32>(), 7);
+        assert_eq!(graph.neighbors(9).count(), 0);
+        assert_eq!(graph.out_degree(1), 2);
+        assert_eq!(graph.out_degree(9), 0);
+        assert_eq!(graph.in_degree(9), 0);
+    }
+
+    #[test]
+    fn in_edges_with_and_without_index() {
+        let mut graph = init();
+        graph.add_edge(4, 4, 7).unwrap();
+        let scanned: Vec<Vec<(i32, i32)>> = (1..=4).map(|id| incoming(&graph, id)).collect();
+        graph.enable_reverse_index();
+        assert!(graph.has_reverse_index());
+        let indexed: Vec<Vec<(i32, i32)>> = (1..=4).map(|id| incoming(&graph, id)).collect();
+        assert_eq!(scanned, indexed);
+        assert_eq!(incoming(&graph, 3), vec![(1, 2), (2, 1), (4, 3)]);
+        assert_eq!(incoming(&graph, 4), vec![(4, 7)]);
+        assert_eq!(graph.in_degree(3), 3);
+
+        graph.add_edge(2, 3, 6).unwrap();
+        graph.remove_edge(1, 3).unwrap();
+        assert_eq!(incoming(&graph, 3), vec![(2, 1), (2, 6), (4, 3)]);
+        graph.remove_node(4).unwrap();
+        assert_eq!(incoming(&graph, 3), vec![(2, 1), (2, 6)]);
+        assert_eq!(graph.in_degree(4), 0);
+        graph.remove_node(1).unwrap();
+        assert_eq!(incoming(&graph, 2), vec![]);
+        assert_eq!(graph.out_degree(3), 0);
+
+        graph.disable_reverse_index();
+        assert_eq!(incoming(&graph, 3), vec![(2, 1), (2, 6)]);
+    }
+
+    #[test]
+    fn undirected_in_edges() {
+        let mut graph = Graph::new_undirected();
+        for id in 1..=3 {
+            graph.add_node(id);
+        }
+        graph.add_edge(1, 2, 4).unwrap();
+        graph.add_edge(3, 2, 5).unwrap();
+        let edges: Vec<(i32, i32)> = graph.in_edges(2).map(|edge| (edge.from(), edge.to())).collect();
+        assert_eq!(edges, vec![(1, 2), (3, 2)]);
+        assert_eq!(graph.in_degree(2), graph.out_degree(2));
+    }
+
+    #[test]
+    fn whole_graph_iterators() {
+        let mut graph = roads();
+        let mut nodes: Vec<char> = graph.nodes().collect();
+        nodes.sort();
+        assert_eq!(nodes, vec!['a', 'b', 'c']);
+        let mut names: Vec<&str> = graph.edges().map(|edge| edge.data().name).collect();
+        names.sort();
+        assert_eq!(names, vec!["A361", "A40", "B4020"]);
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.edge_count(), 3);
+        graph.remove_edge('b', 'a').unwrap();
+        assert_eq!(graph.edge_count(), 2);
+        assert_eq!(graph.edges().count(), 2);
+    }
+
+    #[test]
+    fn error_messages() {
+        assert_eq!(GraphError::MissingTarget(4).to_string(), "edge target 4 is not in the graph");
+        assert_eq!(GraphError::EdgeNotFound('a', 'b').to_string(), "there is no edge from 'a' to 'b'");
+    }
+}
@@ -0,0 +1,84 @@
+use std::hash::Hash;
+
+use super::{Graph, GraphError};
+
+/// Read only view of a graph's structure, shared by Graph and CsrGraph so the
+/// traversals and shortest path search only have to be written once.
+/// Nodes are numbered with dense indices below node_bound(), so walks can keep
+/// their bookkeeping in vectors and only turn indices back into ids when they
+/// hand a node out. Indices stay put for as long as the graph isn't changed.
+pub trait GraphAccess {
+    type Node: Hash+Eq+Copy;
+    type Weight: Ord+Copy;
+    /// Iterator over the edges leaving a node as (target index, weight)
+    type OutEdges<'a>: Iterator<Item=(usize, Self::Weight)> where Self: 'a;
+
+    /// one more than the largest node index, some indices below it may be unused
+    fn node_bound(&self) -> usize;
+
+    /// returns the index of id, None if id isn't in the graph
+    fn node_index(&self, id: Self::Node) -> Option<usize>;
+
+    /// returns the id of the node at index, None if no node has that index
+    fn node_at(&self, index: usize) -> Option<Self::Node>;
+
+    /// Walks the edges leaving the node at index, nothing if no node has that index
+    fn out_edges_at(&self, index: usize) -> Self::OutEdges<'_>;
+
+    /// checks to see if id is a node in the graph
+    fn contains_node(&self, id: Self::Node) -> bool {
+        self.node_index(id).is_some()
+    }
+
+    /// returns the index of id, or an error if id is not a node in the graph
+    fn require_node(&self, id: Self::Node) -> Result<usize, GraphError<Self::Node>> {
+        self.node_index(id).ok_or(GraphError::NodeNotFound(id))
+    }
+}
+
+/// Iterator over the edges leaving a node of a Graph as (target index, weight)
+pub struct IndexedEdges<'a, T: Hash+Eq, W: Ord+Copy, N, E> {
+    graph: &'a Graph<T, W, N, E>,
+    index: usize,
+    slots: std::slice::Iter<'a, usize>
+}
+
+impl<'a, T: Hash+Eq+Copy, W: Ord+Copy, N, E> Iterator for IndexedEdges<'a, T, W, N, E> {
+    type Item = (usize, W);
+    fn next(&mut self) -> Option<Self::Item> {
+        let slot = *self.slots.next()?;
+        let edge = self.graph.edges[slot].as_ref().expect("Nodes only list slots of live edges");
+        let to = if edge.index_from == self.index { edge.index_to } else { edge.index_from };
+        Some((to, edge.weight))
+    }
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy, N, E> GraphAccess for Graph<T, W, N, E> {
+    type Node = T;
+    type Weight = W;
+    type OutEdges<'a> = IndexedEdges<'a, T, W, N, E> where Self: 'a;
+
+    fn node_bound(&self) -> usize {
+        self.node_slots.len()
+    }
+
+    fn node_index(&self, id: T) -> Option<usize> {
+        self.nodes.get(&id).map(|node| node.index)
+    }
+
+    fn node_at(&self, index: usize) -> Option<T> {
+        self.node_slots.get(index).copied().flatten()
+    }
+
+    fn out_edges_at(&self, index: usize) -> IndexedEdges<'_, T, W, N, E> {
+        let slots = match self.node_at(index) {
+            Some(id) => self.edge_slots(id),
+            None => &[]
+        };
+        IndexedEdges {
+            graph: self,
+            index,
+            slots: slots.iter()
+        }
+    }
+}
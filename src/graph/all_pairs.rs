@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::{Add, Sub};
+
+use super::{Graph, ShortestPathError};
+
+/// Shortest distances and next hops between every pair of nodes
+/// Stored as dense matrices indexed by position in nodes(), lookups take node ids
+#[derive(Clone, Debug)]
+pub struct AllPairs<T, W> {
+    ids: Vec<T>,
+    index: HashMap<T, usize>,
+    // row major, entry from * n + to
+    distances: Vec<Option<W>>,
+    next_hops: Vec<Option<usize>>
+}
+
+impl<T: Hash+Eq+Copy, W: Copy> AllPairs<T, W> {
+    fn new(ids: Vec<T>, index: HashMap<T, usize>) -> Self {
+        let size = ids.len() * ids.len();
+        AllPairs {
+            ids,
+            index,
+            distances: vec![None; size],
+            next_hops: vec![None; size]
+        }
+    }
+
+    /// the nodes of the graph, in the order used for the matrix rows and columns
+    pub fn nodes(&self) -> &[T] {
+        &self.ids
+    }
+
+    /// returns the row and column used for id
+    /// returns None if id wasn't in the graph
+    pub fn index_of(&self, id: T) -> Option<usize> {
+        self.index.get(&id).copied()
+    }
+
+    /// returns the length of the shortest path from a to b
+    /// returns None if b can't be reached from a, or either isn't in the graph
+    pub fn distance(&self, a: T, b: T) -> Option<W> {
+        self.distances[self.cell(a, b)?]
+    }
+
+    /// returns the node right after a on the shortest path from a to b
+    /// returns None if a == b, b can't be reached from a, or either isn't in the graph
+    pub fn next_hop(&self, a: T, b: T) -> Option<T> {
+        self.next_hops[self.cell(a, b)?].map(|i| self.ids[i])
+    }
+
+    /// Rebuilds the shortest path from a to b, both ends included
+    /// Returns None if b can't be reached from a, or either isn't in the graph
+    pub fn path(&self, a: T, b: T) -> Option<Vec<T>> {
+        self.distance(a, b)?;
+        let n = self.ids.len();
+        let target = self.index[&b];
+        let mut cur = self.index[&a];
+        let mut path = vec![a];
+        while cur != target {
+            cur = self.next_hops[cur * n + target]?;
+            path.push(self.ids[cur]);
+        }
+        Some(path)
+    }
+
+    fn cell(&self, a: T, b: T) -> Option<usize> {
+        Some(self.index_of(a)? * self.ids.len() + self.index_of(b)?)
+    }
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy+Add<Output=W>+Sub<Output=W>+Default, N, E> Graph<T, W, N, E> {
+    /// Computes every shortest path with the Floyd-Warshall algorithm, O(n^3) time and
+    /// O(n^2) space no matter how many edges there are, so best suited to dense graphs.
+    /// Returns a NegativeCycle error if the graph has a negative cycle anywhere.
+    pub fn floyd_warshall(&self) -> Result<AllPairs<T, W>, ShortestPathError<T>> {
+        let (ids, index) = self.index_nodes();
+        let n = ids.len();
+        let mut pairs = AllPairs::new(ids, index);
+        for i in 0..n {
+            pairs.distances[i * n + i] = Some(W::default());
+        }
+        for edge in self.oriented_edges() {
+            let cell = pairs.index[&edge.node_from] * n + pairs.index[&edge.node_to];
+            let shorter = match pairs.distances[cell] {
+                Some(known) => edge.weight < known,
+                None => true
+            };
+            if shorter {
+                pairs.distances[cell] = Some(edge.weight);
+                pairs.next_hops[cell] = Some(pairs.index[&edge.node_to]);
+            }
+        }
+
+        for via in 0..n {
+            for from in 0..n {
+                let first_leg = match pairs.distances[from * n + via] {
+                    Some(dist) => dist,
+                    None => continue
+                };
+                for to in 0..n {
+                    if let Some(second_leg) = pairs.distances[via * n + to] {
+                        let through = first_leg + second_leg;
+                        let shorter = match pairs.distances[from * n + to] {
+                            Some(known) => through < known,
+                            None => true
+                        };
+                        if shorter {
+                            pairs.distances[from * n + to] = Some(through);
+                            pairs.next_hops[from * n + to] = pairs.next_hops[from * n + via];
+                        }
+                    }
+                }
+                // a node that can get back to itself for less than nothing is on a negative cycle,
+                // stop right away since further rounds keep doubling the distances around it
+                if pairs.distances[from * n + from].is_some_and(|dist| dist < W::default()) {
+                    return match self.potentials() {
+                        Err(cycle) => Err(ShortestPathError::NegativeCycle(cycle)),
+                        Ok(_) => unreachable!("Floyd-Warshall found a negative cycle that Bellman-Ford missed")
+                    };
+                }
+            }
+        }
+        Ok(pairs)
+    }
+
+    /// Computes every shortest path with Johnson's algorithm. Bellman-Ford finds potentials
+    /// that make every edge weight non-negative, and then Dijkstra runs from every node, which
+    /// is O(n m log n) time and much faster than Floyd-Warshall on sparse graphs.
+    /// Returns a NegativeCycle error if the graph has a negative cycle anywhere.
+    pub fn johnson(&self) -> Result<AllPairs<T, W>, ShortestPathError<T>> {
+        let potentials = self.potentials().map_err(ShortestPathError::NegativeCycle)?;
+        let (ids, index) = self.index_nodes();
+        let n = ids.len();
+        let mut pairs = AllPairs::new(ids, index);
+
+        for from in 0..n {
+            let source = pairs.ids[from];
+            let (reweighted, predecessors) = self.dijkstra_with(source, |node, edge| {
+                edge.weight + potentials[&node] - potentials[&edge.node_to]
+            });
+            let row = from * n;
+            for (&id, &dist) in reweighted.iter() {
+                pairs.distances[row + pairs.index[&id]] = Some(dist - potentials[&source] + potentials[&id]);
+            }
+            // the next hop to a node is the next hop to its predecessor, unless the predecessor is the source
+            for &id in reweighted.keys() {
+                let mut chain = Vec::new();
+                let mut cur = id;
+                let hop = loop {
+                    let cell = row + pairs.index[&cur];
+                    if let Some(hop) = pairs.next_hops[cell] {
+                        break Some(hop);
+                    }
+                    match predecessors.get(&cur) {
+                        Some(&prev) if prev == source => break Some(pairs.index[&cur]),
+                        Some(&prev) => {
+                            chain.push(cell);
+                            cur = prev;
+                        },
+                        None => break None
+                    }
+                };
+                if hop.is_some() {
+                    pairs.next_hops[row + pairs.index[&cur]] = hop;
+                    for cell in chain {
+                        pairs.next_hops[cell] = hop;
+                    }
+                }
+            }
+        }
+        Ok(pairs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init() -> Graph<i32, i32> {
+        let mut graph = Graph::new();
+        for id in 1..=5 {
+            graph.add_node(id);
+        }
+        graph.add_edge(1, 2, 3).unwrap();
+        graph.add_edge(1, 3, 8).unwrap();
+        graph.add_edge(1, 5, -4).unwrap();
+        graph.add_edge(2, 4, 1).unwrap();
+        graph.add_edge(2, 5, 7).unwrap();
+        graph.add_edge(3, 2, 4).unwrap();
+        graph.add_edge(4, 1, 2).unwrap();
+        graph.add_edge(4, 3, -5).unwrap();
+        graph.add_edge(5, 4, 6).unwrap();
+        graph
+    }
+
+    // expected distances, row is from and column is to
+    const DISTANCES: [[i32; 5]; 5] = [
+        [0, 1, -3, 2, -4],
+        [3, 0, -4, 1, -1],
+        [7, 4, 0, 5, 3],
+        [2, -1, -5, 0, -2],
+        [8, 5, 1, 6, 0]
+    ];
+
+    fn assert_all_pairs(graph: &Graph<i32, i32>, pairs: &AllPairs<i32, i32>) {
+        for a in 1..=5 {
+            for b in 1..=5 {
+                let expected = DISTANCES[(a - 1) as usize][(b - 1) as usize];
+                assert_eq!(pairs.distance(a, b), Some(expected));
+                let path = pairs.path(a, b).unwrap();
+                assert_eq!(path[0], a);
+                assert_eq!(*path.last().unwrap(), b);
+                let mut total = 0;
+                for step in path.windows(2) {
+                    total += graph.get_edges_from(step[0]).unwrap().iter()
+                        .filter(|&&(to, _)| to == step[1])
+                        .map(|&(_, weight)| weight)
+                        .min()
+                        .unwrap();
+                }
+                assert_eq!(total, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn floyd_warshall() {
+        let graph = init();
+        let pairs = graph.floyd_warshall().unwrap();
+        assert_all_pairs(&graph, &pairs);
+        assert_eq!(pairs.path(1, 2), Some(vec![1, 5, 4, 3, 2]));
+        assert_eq!(pairs.next_hop(1, 2), Some(5));
+        assert_eq!(pairs.next_hop(1, 1), None);
+    }
+
+    #[test]
+    fn johnson() {
+        let graph = init();
+        let pairs = graph.johnson().unwrap();
+        assert_all_pairs(&graph, &pairs);
+        assert_eq!(pairs.path(1, 2), Some(vec![1, 5, 4, 3, 2]));
+        assert_eq!(pairs.next_hop(4, 2), Some(3));
+    }
+
+    #[test]
+    fn unreachable_pairs() {
+        let mut graph = init();
+        graph.add_node(6);
+        graph.add_edge(6, 1, 1).unwrap();
+        for pairs in [graph.floyd_warshall().unwrap(), graph.johnson().unwrap()] {
+            assert_eq!(pairs.distance(6, 3), Some(-2));
+            assert_eq!(pairs.distance(1, 6), None);
+            assert_eq!(pairs.path(1, 6), None);
+            assert_eq!(pairs.next_hop(1, 6), None);
+            assert_eq!(pairs.distance(1, 7), None);
+            assert_eq!(pairs.nodes().len(), 6);
+        }
+    }
+
+    #[test]
+    fn negative_cycle() {
+        let mut graph = init();
+        graph.update_edge_weight(3, 2, -1).unwrap();
+        for result in [graph.floyd_warshall(), graph.johnson()] {
+            match result {
+                Err(ShortestPathError::NegativeCycle(cycle)) => assert!(!cycle.nodes().is_empty()),
+                _ => panic!("expected a negative cycle")
+            }
+        }
+    }
+
+    #[test]
+    fn dense_negative_cycles_stop_early() {
+        // every pair of nodes is a negative cycle, distances overflow if left to keep doubling
+        let mut graph: Graph<usize, i32> = Graph::new();
+        for id in 0..40 {
+            graph.add_node(id);
+        }
+        for from in 0..40 {
+            for to in 0..40 {
+                if from != to {
+                    graph.add_edge(from, to, -1).unwrap();
+                }
+            }
+        }
+        match graph.floyd_warshall() {
+            Err(ShortestPathError::NegativeCycle(cycle)) => assert!(cycle.nodes().len() >= 2),
+            _ => panic!("expected a negative cycle")
+        }
+    }
+
+    #[test]
+    fn agrees_with_dijkstra() {
+        let mut graph = Graph::new_undirected();
+        for id in 0..30 {
+            graph.add_node(id);
+        }
+        for id in 0..30 {
+            graph.add_edge(id, (id * 11 + 5) % 30, (id % 7) + 1).unwrap();
+        }
+        let floyd = graph.floyd_warshall().unwrap();
+        let johnson = graph.johnson().unwrap();
+        for source in 0..30 {
+            let paths = graph.shortest_paths_from(source).unwrap();
+            for target in 0..30 {
+                assert_eq!(floyd.distance(source, target), paths.distance_to(target));
+                assert_eq!(johnson.distance(source, target), paths.distance_to(target));
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Add;
+
+use crate::heap::Heap;
+use super::{Candidate, Graph, GraphError};
+
+/// Outcome of a search for a single path between two nodes
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathSearch<T, W> {
+    route: Option<(Vec<T>, W)>,
+    expanded: usize
+}
+
+impl<T, W: Copy> PathSearch<T, W> {
+    pub(super) fn new(route: Option<(Vec<T>, W)>, expanded: usize) -> Self {
+        PathSearch {
+            route,
+            expanded
+        }
+    }
+
+    /// checks to see if a path was found
+    pub fn is_found(&self) -> bool {
+        self.route.is_some()
+    }
+
+    /// returns the path from start to goal, both ends included
+    /// returns None if the goal can't be reached
+    pub fn path(&self) -> Option<&[T]> {
+        self.route.as_ref().map(|(path, _)| &path[..])
+    }
+
+    /// returns the total weight of the path
+    /// returns None if the goal can't be reached
+    pub fn cost(&self) -> Option<W> {
+        self.route.as_ref().map(|&(_, cost)| cost)
+    }
+
+    /// returns how many nodes were taken off the open set and had their edges looked at
+    pub fn expanded(&self) -> usize {
+        self.expanded
+    }
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy+Add<Output=W>+Default, N, E> Graph<T, W, N, E> {
+    /// Finds a cheapest path from start to goal with A*, using heuristic(node) as the
+    /// estimated remaining cost to the goal. The path is cheapest as long as the heuristic
+    /// never overestimates, and a heuristic that always returns W::default() makes this
+    /// plain Dijkstra stopping at the goal.
+    /// Returns an error if start or goal is not a node in the graph.
+    pub fn astar<F: Fn(T) -> W>(&self, start: T, goal: T, heuristic: F) -> Result<PathSearch<T, W>, GraphError<T>> {
+        self.check_node(start)?;
+        self.check_node(goal)?;
+        let mut best_cost = HashMap::new();
+        let mut predecessors = HashMap::new();
+        let mut open = Heap::new();
+        let mut expanded = 0;
+
+        best_cost.insert(start, W::default());
+        open.push(Reverse(Candidate::new(heuristic(start), (start, W::default()))));
+        while let Some(Reverse(Candidate { node: (node, cost), .. })) = open.pop() {
+            // skip entries that a cheaper path has replaced since they were pushed
+            if cost > best_cost[&node] {
+                continue;
+            }
+            if node == goal {
+                let mut path = vec![goal];
+                let mut cur = goal;
+                while let Some(&prev) = predecessors.get(&cur) {
+                    path.push(prev);
+                    cur = prev;
+                }
+                path.reverse();
+                return Ok(PathSearch {
+                    route: Some((path, cost)),
+                    expanded
+                });
+            }
+            expanded += 1;
+            for edge in self.out_edges(node) {
+                let next_cost = cost + edge.weight;
+                let improved = match best_cost.get(&edge.node_to) {
+                    Some(&known) => next_cost < known,
+                    None => true
+                };
+                if improved {
+                    best_cost.insert(edge.node_to, next_cost);
+                    predecessors.insert(edge.node_to, node);
+                    let estimate = next_cost + heuristic(edge.node_to);
+                    open.push(Reverse(Candidate::new(estimate, (edge.node_to, next_cost))));
+                }
+            }
+        }
+        Ok(PathSearch {
+            route: None,
+            expanded
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Cell = (i32, i32);
+
+    // 4 connected grid with a wall down column 2 that is open at the top row
+    fn grid(size: i32) -> Graph<Cell, i32> {
+        let mut graph = Graph::new_undirected();
+        let open = |(x, y): Cell| x != 2 || y == 0;
+        for x in 0..size {
+            for y in 0..size {
+                if open((x, y)) {
+                    graph.add_node((x, y));
+                }
+            }
+        }
+        for x in 0..size {
+            for y in 0..size {
+                for next in [(x + 1, y), (x, y + 1)] {
+                    if open((x, y)) && graph.contains_node(next) {
+                        graph.add_edge((x, y), next, 1).unwrap();
+                    }
+                }
+            }
+        }
+        graph
+    }
+
+    fn manhattan(goal: Cell) -> impl Fn(Cell) -> i32 {
+        move |(x, y)| (goal.0 - x).abs() + (goal.1 - y).abs()
+    }
+
+    #[test]
+    fn finds_cheapest_path() {
+        let graph = grid(6);
+        let search = graph.astar((0, 5), (5, 5), manhattan((5, 5))).unwrap();
+        assert!(search.is_found());
+        assert_eq!(search.cost(), Some(15));
+        let path = search.path().unwrap();
+        assert_eq!(path.len(), 16);
+        assert_eq!(path[0], (0, 5));
+        assert_eq!(path[15], (5, 5));
+        assert!(path.contains(&(2, 0)));
+    }
+
+    #[test]
+    fn heuristic_expands_fewer_nodes() {
+        let graph = grid(10);
+        let guided = graph.astar((0, 0), (9, 0), manhattan((9, 0))).unwrap();
+        let blind = graph.astar((0, 0), (9, 0), |_| 0).unwrap();
+        assert_eq!(guided.cost(), Some(9));
+        assert_eq!(blind.cost(), Some(9));
+        assert!(guided.expanded() < blind.expanded());
+    }
+
+    #[test]
+    fn zero_heuristic_matches_dijkstra() {
+        let graph = grid(7);
+        let paths = graph.shortest_paths_from((0, 3)).unwrap();
+        for &goal in [(6, 6), (2, 0), (1, 1), (0, 3)].iter() {
+            let search = graph.astar((0, 3), goal, |_| 0).unwrap();
+            assert_eq!(search.cost(), paths.distance_to(goal));
+        }
+    }
+
+    #[test]
+    fn unreachable_goal() {
+        let mut graph = grid(4);
+        graph.add_node((9, 9));
+        let search = graph.astar((0, 0), (9, 9), manhattan((9, 9))).unwrap();
+        assert!(!search.is_found());
+        assert_eq!(search.path(), None);
+        assert_eq!(search.cost(), None);
+        assert_eq!(search.expanded(), 13);
+        assert_eq!(graph.astar((0, 0), (2, 2), |_| 0).err(), Some(GraphError::NodeNotFound((2, 2))));
+    }
+}
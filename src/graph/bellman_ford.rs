@@ -0,0 +1,288 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+use std::ops::{Add, Sub};
+
+use crate::queue::Queue;
+use super::{Graph, GraphError, ShortestPaths};
+
+/// A cycle whose edge weights add up to less than zero, in walk order
+/// The last node has an edge back to the first
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NegativeCycle<T> {
+    nodes: Vec<T>
+}
+
+impl<T> NegativeCycle<T> {
+    pub(super) fn new(nodes: Vec<T>) -> Self {
+        NegativeCycle {
+            nodes
+        }
+    }
+
+    /// the nodes making up the cycle, in the order the edges visit them
+    pub fn nodes(&self) -> &[T] {
+        &self.nodes
+    }
+}
+
+/// Errors returned by the shortest path searches that allow negative edge weights
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShortestPathError<T> {
+    /// a node passed in isn't in the graph
+    Graph(GraphError<T>),
+    /// a negative cycle can be reached from the source, so some distances have no minimum
+    NegativeCycle(NegativeCycle<T>)
+}
+
+impl<T> From<GraphError<T>> for ShortestPathError<T> {
+    fn from(err: GraphError<T>) -> Self {
+        ShortestPathError::Graph(err)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Display for ShortestPathError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShortestPathError::Graph(err) => err.fmt(f),
+            ShortestPathError::NegativeCycle(cycle) => write!(f, "graph contains a negative cycle through {:?}", cycle.nodes)
+        }
+    }
+}
+
+impl<T: fmt::Debug> Error for ShortestPathError<T> {}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy+Add<Output=W>+Sub<Output=W>+Default, N, E> Graph<T, W, N, E> {
+    /// Finds shortest paths from source with the Bellman-Ford algorithm, which unlike
+    /// Dijkstra works with negative edge weights. W::default() is used as the zero distance.
+    /// Note that a negative edge in an undirected graph is a negative cycle on its own.
+    /// Returns a NegativeCycle error if a negative cycle can be reached from source.
+    pub fn bellman_ford(&self, source: T) -> Result<ShortestPaths<T, W>, ShortestPathError<T>> {
+        self.check_node(source)?;
+        let mut distances = HashMap::new();
+        let mut predecessors = HashMap::new();
+        distances.insert(source, W::default());
+
+        // without a negative cycle every shortest path is settled after nodes - 1 rounds
+        for _ in 1..self.nodes.len() {
+            if self.relax_all(&mut distances, &mut predecessors).is_none() {
+                break;
+            }
+        }
+        if let Some(node) = self.relax_all(&mut distances, &mut predecessors) {
+            match predecessor_cycle(node, &predecessors) {
+                Some(nodes) => return Err(ShortestPathError::NegativeCycle(NegativeCycle { nodes })),
+                None => unreachable!("A distance improved after every round, so the predecessors must loop")
+            }
+        }
+        Ok(ShortestPaths::new(source, distances, predecessors, self.nodes.keys().copied()))
+    }
+
+    /// Finds shortest paths from source with the queue based SPFA variant of Bellman-Ford,
+    /// which only revisits nodes whose distance just improved. Results match bellman_ford.
+    /// Returns a NegativeCycle error if a negative cycle can be reached from source.
+    pub fn spfa(&self, source: T) -> Result<ShortestPaths<T, W>, ShortestPathError<T>> {
+        self.check_node(source)?;
+        let mut distances = HashMap::new();
+        let mut predecessors = HashMap::new();
+        // how many edges the current best path to each node uses
+        let mut path_edges = HashMap::new();
+        let mut queue = Queue::new();
+        let mut queued = HashSet::new();
+
+        distances.insert(source, W::default());
+        path_edges.insert(source, 0);
+        queue.push(source);
+        queued.insert(source);
+        while let Some(node) = queue.pop() {
+            queued.remove(&node);
+            let dist = distances[&node];
+            let edge_count = path_edges[&node] + 1;
+            for edge in self.out_edges(node) {
+                let next_dist = dist + edge.weight;
+                let improved = match distances.get(&edge.node_to) {
+                    Some(&known) => next_dist < known,
+                    None => true
+                };
+                if !improved {
+                    continue;
+                }
+                distances.insert(edge.node_to, next_dist);
+                predecessors.insert(edge.node_to, node);
+                path_edges.insert(edge.node_to, edge_count);
+                // a shortest path can't use more edges than there are nodes
+                if edge_count >= self.nodes.len() {
+                    return match predecessor_cycle(edge.node_to, &predecessors) {
+                        Some(nodes) => Err(ShortestPathError::NegativeCycle(NegativeCycle { nodes })),
+                        None => self.bellman_ford(source)
+                    };
+                }
+                if queued.insert(edge.node_to) {
+                    queue.push(edge.node_to);
+                }
+            }
+        }
+        Ok(ShortestPaths::new(source, distances, predecessors, self.nodes.keys().copied()))
+    }
+
+    // Bellman-Ford from a virtual source with a zero weight edge to every node. The resulting
+    // distances are potentials h where weight + h(from) - h(to) is never negative, as long as
+    // the graph has no negative cycle anywhere.
+    pub(super) fn potentials(&self) -> Result<HashMap<T, W>, NegativeCycle<T>> {
+        let mut distances: HashMap<T, W> = self.nodes.keys().map(|&id| (id, W::default())).collect();
+        let mut predecessors = HashMap::new();
+        // the virtual source makes for one extra node, so one extra round
+        for _ in 0..self.nodes.len() {
+            if self.relax_all(&mut distances, &mut predecessors).is_none() {
+                return Ok(distances);
+            }
+        }
+        match self.relax_all(&mut distances, &mut predecessors) {
+            None => Ok(distances),
+            Some(node) => match predecessor_cycle(node, &predecessors) {
+                Some(nodes) => Err(NegativeCycle { nodes }),
+                None => unreachable!("A distance improved after every round, so the predecessors must loop")
+            }
+        }
+    }
+
+    // one Bellman-Ford round over every edge, returns the last node whose distance improved
+    fn relax_all(&self, distances: &mut HashMap<T, W>, predecessors: &mut HashMap<T, T>) -> Option<T> {
+        let mut changed = None;
+        for edge in self.oriented_edges() {
+            let dist = match distances.get(&edge.node_from) {
+                Some(&dist) => dist,
+                None => continue
+            };
+            let next_dist = dist + edge.weight;
+            let improved = match distances.get(&edge.node_to) {
+                Some(&known) => next_dist < known,
+                None => true
+            };
+            if improved {
+                distances.insert(edge.node_to, next_dist);
+                predecessors.insert(edge.node_to, edge.node_from);
+                changed = Some(edge.node_to);
+            }
+        }
+        changed
+    }
+}
+
+// walks predecessors back from start until a node repeats, returning the loop in edge order
+pub(super) fn predecessor_cycle<T: Hash+Eq+Copy>(start: T, predecessors: &HashMap<T, T>) -> Option<Vec<T>> {
+    let mut seen = HashSet::new();
+    let mut cur = start;
+    while seen.insert(cur) {
+        cur = *predecessors.get(&cur)?;
+    }
+    let mut nodes = vec![cur];
+    let mut member = predecessors[&cur];
+    while member != cur {
+        nodes.push(member);
+        member = predecessors[&member];
+    }
+    nodes.reverse();
+    Some(nodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init() -> Graph<char, i32> {
+        let mut graph = Graph::new();
+        for id in "stxyz".chars() {
+            graph.add_node(id);
+        }
+        graph.add_edge('s', 't', 6).unwrap();
+        graph.add_edge('s', 'y', 7).unwrap();
+        graph.add_edge('t', 'x', 5).unwrap();
+        graph.add_edge('t', 'y', 8).unwrap();
+        graph.add_edge('t', 'z', -4).unwrap();
+        graph.add_edge('x', 't', -2).unwrap();
+        graph.add_edge('y', 'x', -3).unwrap();
+        graph.add_edge('y', 'z', 9).unwrap();
+        graph.add_edge('z', 's', 2).unwrap();
+        graph.add_edge('z', 'x', 7).unwrap();
+        graph
+    }
+
+    fn assert_negative_cycle(graph: &Graph<char, i32>, err: ShortestPathError<char>) {
+        let cycle = match err {
+            ShortestPathError::NegativeCycle(cycle) => cycle,
+            other => panic!("expected a negative cycle, got {:?}", other)
+        };
+        let nodes = cycle.nodes();
+        let mut total = 0;
+        for i in 0..nodes.len() {
+            let next = nodes[(i + 1) % nodes.len()];
+            let weight = graph.get_edges_from(nodes[i]).unwrap().iter()
+                .filter(|&&(to, _)| to == next)
+                .map(|&(_, weight)| weight)
+                .min()
+                .expect("consecutive cycle nodes should share an edge");
+            total += weight;
+        }
+        assert!(total < 0);
+    }
+
+    #[test]
+    fn negative_weights() {
+        let graph = init();
+        for paths in [graph.bellman_ford('s').unwrap(), graph.spfa('s').unwrap()] {
+            assert_eq!(paths.distance_to('s'), Some(0));
+            assert_eq!(paths.distance_to('t'), Some(2));
+            assert_eq!(paths.distance_to('x'), Some(4));
+            assert_eq!(paths.distance_to('y'), Some(7));
+            assert_eq!(paths.distance_to('z'), Some(-2));
+            assert_eq!(paths.path_to('z'), Some(vec!['s', 'y', 'x', 't', 'z']));
+        }
+    }
+
+    #[test]
+    fn matches_dijkstra_without_negative_weights() {
+        let mut graph = Graph::new();
+        for id in 0..20 {
+            graph.add_node(id);
+        }
+        for id in 0..20 {
+            graph.add_edge(id, (id * 7 + 3) % 20, id % 5 + 1).unwrap();
+            graph.add_edge(id, (id + 1) % 20, 4).unwrap();
+        }
+        let dijkstra = graph.shortest_paths_from(0).unwrap();
+        assert_eq!(graph.bellman_ford(0).unwrap().distances(), dijkstra.distances());
+        assert_eq!(graph.spfa(0).unwrap().distances(), dijkstra.distances());
+    }
+
+    #[test]
+    fn negative_cycle_detected() {
+        let mut graph = init();
+        graph.update_edge_weight('z', 's', -5).unwrap();
+        assert_negative_cycle(&graph, graph.bellman_ford('s').unwrap_err());
+        assert_negative_cycle(&graph, graph.spfa('s').unwrap_err());
+        assert_negative_cycle(&graph, graph.spfa('y').unwrap_err());
+    }
+
+    #[test]
+    fn unreachable_negative_cycle_is_ignored() {
+        let mut graph = init();
+        graph.add_node('a');
+        graph.add_node('b');
+        graph.add_edge('a', 'b', -1).unwrap();
+        graph.add_edge('b', 'a', -1).unwrap();
+        let paths = graph.bellman_ford('s').unwrap();
+        assert_eq!(paths.distance_to('z'), Some(-2));
+        assert!(!paths.is_reachable('a'));
+        assert!(graph.spfa('s').is_ok());
+        assert!(graph.spfa('a').is_err());
+    }
+
+    #[test]
+    fn missing_source() {
+        let graph = init();
+        assert_eq!(graph.bellman_ford('q').unwrap_err(), ShortestPathError::Graph(GraphError::NodeNotFound('q')));
+        assert_eq!(graph.spfa('q').unwrap_err().to_string(), "node 'q' is not in the graph");
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use super::{Direction, Graph, GraphError};
+
+// edges as (from, to, weight)
+type EdgeSet<T, W> = Vec<(T, T, W)>;
+
+// everything found by one depth first pass of the Hopcroft-Tarjan algorithm
+struct Biconnectivity<T, W> {
+    articulation_points: Vec<T>,
+    bridges: EdgeSet<T, W>,
+    components: Vec<EdgeSet<T, W>>
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy, N, E> Graph<T, W, N, E> {
+    /// Finds the nodes whose removal disconnects part of their connected component
+    /// Returns an error if the graph is directed
+    pub fn articulation_points(&self) -> Result<Vec<T>, GraphError<T>> {
+        Ok(self.biconnectivity()?.articulation_points)
+    }
+
+    /// Finds the edges whose removal disconnects part of their connected component, as
+    /// (from, to, weight). One of a pair of parallel edges is never a bridge.
+    /// Returns an error if the graph is directed
+    pub fn bridges(&self) -> Result<Vec<(T, T, W)>, GraphError<T>> {
+        Ok(self.biconnectivity()?.bridges)
+    }
+
+    /// Splits the edges into biconnected components, the largest groups of edges that stay
+    /// connected with any single node removed. Articulation points belong to more than one
+    /// component, a bridge is a component on its own, and self loops are left out.
+    /// Returns an error if the graph is directed
+    pub fn biconnected_components(&self) -> Result<Vec<EdgeSet<T, W>>, GraphError<T>> {
+        Ok(self.biconnectivity()?.components)
+    }
+
+    fn biconnectivity(&self) -> Result<Biconnectivity<T, W>, GraphError<T>> {
+        self.require_direction(Direction::Undirected)?;
+        let mut discovered: HashMap<T, usize> = HashMap::with_capacity(self.nodes.len());
+        let mut low: HashMap<T, usize> = HashMap::with_capacity(self.nodes.len());
+        let mut articulation_points = HashSet::new();
+        let mut bridges = Vec::new();
+        let mut components = Vec::new();
+        // slots of the edges walked but not yet assigned to a component
+        let mut edge_stack: Vec<usize> = Vec::new();
+        // (node, slot of the tree edge that led here, index of the next edge to look at)
+        let mut calls: Vec<(T, Option<usize>, usize)> = Vec::new();
+
+        for &root in self.nodes.keys() {
+            if discovered.contains_key(&root) {
+                continue;
+            }
+            discovered.insert(root, discovered.len());
+            low.insert(root, discovered[&root]);
+            calls.push((root, None, 0));
+            let mut root_children = 0;
+
+            while let Some(&(node, tree_slot, next_edge)) = calls.last() {
+                let slots = self.edge_slots(node);
+                if next_edge < slots.len() {
+                    calls.last_mut().unwrap().2 += 1;
+                    let slot = slots[next_edge];
+                    // only the exact edge used to get here is skipped, so parallel edges count as cycles
+                    if Some(slot) == tree_slot {
+                        continue;
+                    }
+                    let child = self.edge_at(node, slot).node_to;
+                    match discovered.get(&child) {
+                        None => {
+                            discovered.insert(child, discovered.len());
+                            low.insert(child, discovered[&child]);
+                            edge_stack.push(slot);
+                            calls.push((child, Some(slot), 0));
+                            if node == root {
+                                root_children += 1;
+                            }
+                        },
+                        // the other end of an edge already walked from child, or a self loop
+                        Some(&order) if order >= discovered[&node] => {},
+                        Some(&order) => {
+                            edge_stack.push(slot);
+                            let node_low = low.get_mut(&node).unwrap();
+                            *node_low = (*node_low).min(order);
+                        }
+                    }
+                } else {
+                    calls.pop();
+                    let (parent, tree_slot) = match (calls.last(), tree_slot) {
+                        (Some(&(parent, _, _)), Some(slot)) => (parent, slot),
+                        _ => continue
+                    };
+                    let node_low = low[&node];
+                    let parent_low = low.get_mut(&parent).unwrap();
+                    *parent_low = (*parent_low).min(node_low);
+                    if node_low < discovered[&parent] {
+                        continue;
+                    }
+                    // nothing below node reaches above parent, so parent separates node's subtree
+                    if parent != root {
+                        articulation_points.insert(parent);
+                    }
+                    if node_low > discovered[&parent] {
+                        bridges.push(self.stored_edge(tree_slot));
+                    }
+                    let mut component = Vec::new();
+                    while let Some(slot) = edge_stack.pop() {
+                        component.push(self.stored_edge(slot));
+                        if slot == tree_slot {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+            if root_children > 1 {
+                articulation_points.insert(root);
+            }
+        }
+        Ok(Biconnectivity {
+            articulation_points: articulation_points.into_iter().collect(),
+            bridges,
+            components
+        })
+    }
+
+    // the edge in slot as it was added
+    fn stored_edge(&self, slot: usize) -> (T, T, W) {
+        let edge = self.edges[slot].as_ref().expect("Nodes only list slots of live edges");
+        (edge.node_from, edge.node_to, edge.weight)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //  1 - 2      6 - 7
+    //  | /        |   |
+    //  3 - 4 - 5  9 - 8
+    //      |
+    //      10 = 11 (parallel edges)
+    fn init() -> Graph<i32, i32> {
+        let mut graph = Graph::new_undirected();
+        for id in 1..=11 {
+            graph.add_node(id);
+        }
+        for &(from, to) in [(1, 2), (2, 3), (3, 1), (3, 4), (4, 5), (4, 10), (10, 11), (11, 10), (6, 7), (7, 8), (8, 9), (9, 6)].iter() {
+            graph.add_edge(from, to, from * 100 + to).unwrap();
+        }
+        graph.add_edge(5, 5, 0).unwrap();
+        graph
+    }
+
+    fn sorted<X: Ord>(mut items: Vec<X>) -> Vec<X> {
+        items.sort();
+        items
+    }
+
+    // edges without weights with the smaller end first
+    fn ends(edges: &[(i32, i32, i32)]) -> Vec<(i32, i32)> {
+        sorted(edges.iter().map(|&(a, b, _)| (a.min(b), a.max(b))).collect())
+    }
+
+    #[test]
+    fn articulation_points() {
+        let graph = init();
+        assert_eq!(sorted(graph.articulation_points().unwrap()), vec![3, 4, 10]);
+    }
+
+    #[test]
+    fn bridges() {
+        let graph = init();
+        let bridges = graph.bridges().unwrap();
+        assert_eq!(ends(&bridges), vec![(3, 4), (4, 5), (4, 10)]);
+        assert!(bridges.contains(&(3, 4, 304)));
+    }
+
+    #[test]
+    fn components() {
+        let graph = init();
+        let components = graph.biconnected_components().unwrap();
+        let mut all: Vec<Vec<(i32, i32)>> = components.iter().map(|edges| ends(edges)).collect();
+        all.sort();
+        assert_eq!(all, vec![
+            vec![(1, 2), (1, 3), (2, 3)],
+            vec![(3, 4)],
+            vec![(4, 5)],
+            vec![(4, 10)],
+            vec![(6, 7), (6, 9), (7, 8), (8, 9)],
+            vec![(10, 11), (10, 11)]
+        ]);
+    }
+
+    #[test]
+    fn root_with_several_children() {
+        let graph = Graph::<usize, u32>::star(5, Direction::Undirected, 1);
+        assert_eq!(graph.articulation_points().unwrap(), vec![0]);
+        assert_eq!(graph.bridges().unwrap().len(), 4);
+        let cycle = Graph::<usize, u32>::cycle(5, Direction::Undirected, 1);
+        assert!(cycle.articulation_points().unwrap().is_empty());
+        assert!(cycle.bridges().unwrap().is_empty());
+        assert_eq!(cycle.biconnected_components().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn long_path() {
+        let graph = Graph::<usize, u32>::path(100_000, Direction::Undirected, 1);
+        assert_eq!(graph.articulation_points().unwrap().len(), 99_998);
+        assert_eq!(graph.bridges().unwrap().len(), 99_999);
+    }
+
+    #[test]
+    fn directed_graphs_are_rejected() {
+        let graph: Graph<i32, i32> = Graph::new();
+        assert_eq!(graph.bridges(), Err(GraphError::UnsupportedDirection(Direction::Directed)));
+    }
+}
@@ -0,0 +1,638 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+use std::ops::{Add, Sub};
+
+use crate::queue::Queue;
+use super::{Graph, GraphError};
+
+/// Which of the two sides of a bipartite graph a node is on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    Left,
+    Right
+}
+
+impl Side {
+    fn other(self) -> Side {
+        match self {
+            Side::Left => Side::Right,
+            Side::Right => Side::Left
+        }
+    }
+}
+
+/// Two coloring of a graph where every edge goes between the two sides
+#[derive(Clone, Debug)]
+pub struct Bipartition<T> {
+    sides: HashMap<T, Side>
+}
+
+impl<T: Hash+Eq+Copy> Bipartition<T> {
+    /// returns the side id is on
+    /// returns None if id wasn't in the graph
+    pub fn side_of(&self, id: T) -> Option<Side> {
+        self.sides.get(&id).copied()
+    }
+
+    /// the nodes on the left side
+    pub fn left(&self) -> Vec<T> {
+        self.on_side(Side::Left)
+    }
+
+    /// the nodes on the right side
+    pub fn right(&self) -> Vec<T> {
+        self.on_side(Side::Right)
+    }
+
+    fn on_side(&self, side: Side) -> Vec<T> {
+        self.sides.iter().filter(|&(_, &s)| s == side).map(|(&id, _)| id).collect()
+    }
+}
+
+/// Error returned when a graph isn't bipartite
+/// Holds a cycle with an odd number of edges in walk order, the last node has an edge back to the first
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OddCycle<T> {
+    nodes: Vec<T>
+}
+
+impl<T> OddCycle<T> {
+    /// the nodes making up the cycle, in the order the edges visit them
+    pub fn nodes(&self) -> &[T] {
+        &self.nodes
+    }
+}
+
+impl<T: fmt::Debug> fmt::Display for OddCycle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "graph is not bipartite, it has an odd cycle through {:?}", self.nodes)
+    }
+}
+
+impl<T: fmt::Debug> Error for OddCycle<T> {}
+
+/// Set of edges in a bipartite graph where no two edges share a node
+#[derive(Clone, Debug)]
+pub struct Matching<T, W> {
+    pairs: Vec<(T, T)>,
+    mates: HashMap<T, T>,
+    total_weight: W
+}
+
+impl<T: Hash+Eq+Copy, W: Copy> Matching<T, W> {
+    fn new(pairs: Vec<(T, T)>, total_weight: W) -> Self {
+        let mut mates = HashMap::new();
+        for &(a, b) in &pairs {
+            mates.insert(a, b);
+            mates.insert(b, a);
+        }
+        Matching {
+            pairs,
+            mates,
+            total_weight
+        }
+    }
+
+    /// the matched pairs as (left, right)
+    pub fn pairs(&self) -> &[(T, T)] {
+        &self.pairs
+    }
+
+    /// returns the number of matched pairs
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    /// checks to see if nothing was matched
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+
+    /// returns the node id is matched with
+    /// returns None if id is unmatched
+    pub fn mate(&self, id: T) -> Option<T> {
+        self.mates.get(&id).copied()
+    }
+
+    /// the sum of the matched edges' weights, using the lightest of any parallel edges
+    pub fn total_weight(&self) -> W {
+        self.total_weight
+    }
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy, N, E> Graph<T, W, N, E> {
+    /// Tries to split the nodes into two sides with every edge going between them,
+    /// ignoring edge direction. Returns an odd cycle if there is no such split.
+    pub fn is_bipartite(&self) -> Result<Bipartition<T>, OddCycle<T>> {
+        let neighbors = self.undirected_neighbors();
+        let mut sides = HashMap::with_capacity(self.nodes.len());
+        // breadth first tree used to rebuild an odd cycle, node -> (parent, depth)
+        let mut tree: HashMap<T, (Option<T>, usize)> = HashMap::with_capacity(self.nodes.len());
+        let mut queue = Queue::new();
+
+        for &root in self.nodes.keys() {
+            if sides.contains_key(&root) {
+                continue;
+            }
+            sides.insert(root, Side::Left);
+            tree.insert(root, (None, 0));
+            queue.push(root);
+            while let Some(node) = queue.pop() {
+                let side = sides[&node];
+                let depth = tree[&node].1;
+                for &next in &neighbors[&node] {
+                    match sides.get(&next) {
+                        None => {
+                            sides.insert(next, side.other());
+                            tree.insert(next, (Some(node), depth + 1));
+                            queue.push(next);
+                        },
+                        Some(&next_side) if next_side == side => {
+                            return Err(OddCycle { nodes: odd_cycle(&tree, node, next) });
+                        },
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+        Ok(Bipartition { sides })
+    }
+}
+
+// a and b share a side and an edge, so their tree paths up to the common ancestor plus that edge
+// make an odd cycle, returned as ancestor -> ... -> a -> b -> ... back up to just below the ancestor
+fn odd_cycle<T: Hash+Eq+Copy>(tree: &HashMap<T, (Option<T>, usize)>, a: T, b: T) -> Vec<T> {
+    let mut down = vec![a];
+    let mut up = vec![b];
+    let (mut x, mut y) = (a, b);
+    while x != y {
+        let (x_parent, x_depth) = tree[&x];
+        let (y_parent, y_depth) = tree[&y];
+        if x_depth >= y_depth {
+            x = x_parent.unwrap();
+            down.push(x);
+        }
+        if y_depth >= x_depth {
+            y = y_parent.unwrap();
+            up.push(y);
+        }
+    }
+    // both paths end at the common ancestor, keep it once
+    up.pop();
+    down.reverse();
+    down.extend(up);
+    down
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy+Add<Output=W>+Sub<Output=W>+Default, N, E> Graph<T, W, N, E> {
+    /// Finds a maximum cardinality matching with the Hopcroft-Karp algorithm, ignoring edge direction.
+    /// Pairs are given as (left, right) using the sides from is_bipartite.
+    /// Returns an odd cycle if the graph isn't bipartite.
+    pub fn maximum_matching(&self) -> Result<Matching<T, W>, OddCycle<T>> {
+        let bipartition = self.is_bipartite()?;
+        let left = bipartition.left();
+        let right = bipartition.right();
+        let right_index: HashMap<T, usize> = right.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+        let costs = self.cross_costs(&left, &right_index);
+        let adjacency: Vec<Vec<usize>> = costs.iter().map(|row| row.keys().copied().collect()).collect();
+
+        let mut left_mate: Vec<Option<usize>> = vec![None; left.len()];
+        let mut right_mate: Vec<Option<usize>> = vec![None; right.len()];
+        while let Some(layer) = matching_layers(&adjacency, &left_mate, &right_mate) {
+            let mut next_edge = vec![0; left.len()];
+            let mut layer = layer;
+            for start in 0..left.len() {
+                if left_mate[start].is_none() {
+                    augment_from(start, &adjacency, &mut layer, &mut next_edge, &mut left_mate, &mut right_mate);
+                }
+            }
+        }
+
+        let mut pairs = Vec::new();
+        let mut total_weight = W::default();
+        for (l, mate) in left_mate.iter().enumerate() {
+            if let Some(r) = *mate {
+                pairs.push((left[l], right[r]));
+                total_weight = total_weight + costs[l][&r];
+            }
+        }
+        Ok(Matching::new(pairs, total_weight))
+    }
+
+    /// Solves the weighted assignment problem with the Hungarian algorithm, matching every node
+    /// of the smaller side to a distinct node of the other side so the total edge weight is
+    /// as small as possible. left picks the nodes on one side, everything else is the other
+    /// side, and only edges between the two sides are used, ignoring direction.
+    /// Returns None if the smaller side can't be completely matched.
+    /// Returns an error if a node in left is not in the graph.
+    pub fn min_cost_assignment(&self, left: &[T]) -> Result<Option<Matching<T, W>>, GraphError<T>> {
+        for &id in left {
+            self.check_node(id)?;
+        }
+        let left_set: HashSet<T> = left.iter().copied().collect();
+        let left: Vec<T> = left_set.iter().copied().collect();
+        let right: Vec<T> = self.nodes.keys().filter(|id| !left_set.contains(id)).copied().collect();
+        // the algorithm assigns every row, so the smaller side has to be the rows
+        let flipped = left.len() > right.len();
+        let (rows, columns) = if flipped { (right, left) } else { (left, right) };
+        let column_index: HashMap<T, usize> = columns.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+        let costs = self.cross_costs(&rows, &column_index);
+
+        let assigned = match hungarian(&costs, columns.len()) {
+            Some(assigned) => assigned,
+            None => return Ok(None)
+        };
+        let mut pairs = Vec::new();
+        let mut total_weight = W::default();
+        for (row, &column) in assigned.iter().enumerate() {
+            total_weight = total_weight + costs[row][&column];
+            if flipped {
+                pairs.push((columns[column], rows[row]));
+            } else {
+                pairs.push((rows[row], columns[column]));
+            }
+        }
+        Ok(Some(Matching::new(pairs, total_weight)))
+    }
+
+    // cheapest edge from each row node to each column node it touches, ignoring direction
+    fn cross_costs(&self, rows: &[T], column_index: &HashMap<T, usize>) -> Vec<HashMap<usize, W>> {
+        let row_index: HashMap<T, usize> = rows.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+        let mut costs = vec![HashMap::new(); rows.len()];
+        for edge in self.oriented_edges() {
+            let ends = [(edge.node_from, edge.node_to), (edge.node_to, edge.node_from)];
+            for (row, column) in ends.iter() {
+                if let (Some(&r), Some(&c)) = (row_index.get(row), column_index.get(column)) {
+                    let cost = costs[r].entry(c).or_insert(edge.weight);
+                    if edge.weight < *cost {
+                        *cost = edge.weight;
+                    }
+                }
+            }
+        }
+        costs
+    }
+}
+
+// Hopcroft-Karp phase setup, breadth first layers of left nodes starting from the free ones,
+// stopping at the first layer that touches a free right node so only shortest augmenting
+// paths are followed. Returns None once no augmenting path is left
+fn matching_layers(adjacency: &[Vec<usize>], left_mate: &[Option<usize>], right_mate: &[Option<usize>]) -> Option<Vec<Option<usize>>> {
+    let mut layer = vec![None; adjacency.len()];
+    let mut queue = Queue::new();
+    for (l, mate) in left_mate.iter().enumerate() {
+        if mate.is_none() {
+            layer[l] = Some(0);
+            queue.push(l);
+        }
+    }
+    // depth of the first layer with an edge to a free right node
+    let mut free_depth = None;
+    while let Some(l) = queue.pop() {
+        let depth = layer[l].unwrap();
+        if free_depth.is_some_and(|free| depth > free) {
+            break;
+        }
+        for &r in &adjacency[l] {
+            match right_mate[r] {
+                None => free_depth = Some(depth),
+                Some(next) if layer[next].is_none() => {
+                    layer[next] = Some(depth + 1);
+                    queue.push(next);
+                },
+                Some(_) => {}
+            }
+        }
+    }
+    let free_depth = free_depth?;
+    // nodes queued past the free layer only lead to longer paths
+    for depth in layer.iter_mut() {
+        if depth.is_some_and(|d| d > free_depth) {
+            *depth = None;
+        }
+    }
+    Some(layer)
+}
+
+// iterative depth first search for an augmenting path along the layers, flipping it if one is found
+fn augment_from(start: usize, adjacency: &[Vec<usize>], layer: &mut [Option<usize>], next_edge: &mut [usize],
+                left_mate: &mut [Option<usize>], right_mate: &mut [Option<usize>]) -> bool {
+    let mut path: Vec<(usize, usize)> = Vec::new();
+    let mut node = start;
+    loop {
+        let mut step = None;
+        while next_edge[node] < adjacency[node].len() {
+            let r = adjacency[node][next_edge[node]];
+            next_edge[node] += 1;
+            match right_mate[r] {
+                None => {
+                    path.push((node, r));
+                    for &(l, r) in &path {
+                        left_mate[l] = Some(r);
+                        right_mate[r] = Some(l);
+                    }
+                    return true;
+                },
+                Some(next) if layer[next] == layer[node].map(|d| d + 1) => {
+                    step = Some((r, next));
+                    break;
+                },
+                Some(_) => {}
+            }
+        }
+        match step {
+            Some((r, next)) => {
+                path.push((node, r));
+                node = next;
+            },
+            None => {
+                // nothing left to try from here this phase
+                layer[node] = None;
+                match path.pop() {
+                    Some((prev, _)) => node = prev,
+                    None => return false
+                }
+            }
+        }
+    }
+}
+
+// Hungarian algorithm with potentials over rows x columns, rows <= columns, missing entries
+// can't be assigned. Returns the column given to each row, or None if some row can't get one.
+fn hungarian<W: Ord+Copy+Add<Output=W>+Sub<Output=W>+Default>(costs: &[HashMap<usize, W>], columns: usize) -> Option<Vec<usize>> {
+    let zero = W::default();
+    // index 0 is a placeholder row and column, real ones start at 1. Row potentials only grow
+    // and column potentials only shrink, so the columns keep how far they have dropped instead,
+    // which keeps every value non-negative for unsigned weights
+    let mut row_potential = vec![zero; costs.len() + 1];
+    let mut column_drop = vec![zero; columns + 1];
+    let mut column_row = vec![0; columns + 1];
+    let mut way = vec![0; columns + 1];
+
+    for row in 1..=costs.len() {
+        column_row[0] = row;
+        let mut column = 0;
+        let mut slack: Vec<Option<W>> = vec![None; columns + 1];
+        let mut used = vec![false; columns + 1];
+        loop {
+            used[column] = true;
+            let current_row = column_row[column];
+            let mut delta = None;
+            let mut next_column = 0;
+            for c in 1..=columns {
+                if used[c] {
+                    continue;
+                }
+                if let Some(&cost) = costs[current_row - 1].get(&(c - 1)) {
+                    // never negative, so adding before subtracting can't underflow
+                    let reduced = cost + column_drop[c] - row_potential[current_row];
+                    let improved = match slack[c] {
+                        Some(s) => reduced < s,
+                        None => true
+                    };
+                    if improved {
+                        slack[c] = Some(reduced);
+                        way[c] = column;
+                    }
+                }
+                if let Some(s) = slack[c] {
+                    let smaller = match delta {
+                        Some(d) => s < d,
+                        None => true
+                    };
+                    if smaller {
+                        delta = Some(s);
+                        next_column = c;
+                    }
+                }
+            }
+            // no column can be reached from the rows tried so far
+            let delta = delta?;
+            for c in 0..=columns {
+                if used[c] {
+                    row_potential[column_row[c]] = row_potential[column_row[c]] + delta;
+                    column_drop[c] = column_drop[c] + delta;
+                } else if let Some(s) = slack[c] {
+                    slack[c] = Some(s - delta);
+                }
+            }
+            column = next_column;
+            if column_row[column] == 0 {
+                break;
+            }
+        }
+        // flip the alternating path back to the placeholder column
+        while column != 0 {
+            let prev = way[column];
+            column_row[column] = column_row[prev];
+            column = prev;
+        }
+    }
+
+    let mut assigned = vec![0; costs.len()];
+    for c in 1..=columns {
+        if column_row[c] != 0 {
+            assigned[column_row[c] - 1] = c - 1;
+        }
+    }
+    Some(assigned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+
+    fn assert_odd_cycle<W: Ord+Copy>(graph: &Graph<i32, W>, cycle: &OddCycle<i32>) {
+        let nodes = cycle.nodes();
+        assert_eq!(nodes.len() % 2, 1);
+        for i in 0..nodes.len() {
+            let (a, b) = (nodes[i], nodes[(i + 1) % nodes.len()]);
+            assert!(graph.contains_edge(a, b) || graph.contains_edge(b, a));
+        }
+    }
+
+    fn workers() -> Graph<i32, i32> {
+        // jobs 1-3, workers 10-13
+        let mut graph = Graph::new_undirected();
+        for id in [1, 2, 3, 10, 11, 12, 13] {
+            graph.add_node(id);
+        }
+        let costs = [(1, 10, 9), (1, 11, 2), (1, 12, 7), (2, 10, 6), (2, 11, 4), (2, 12, 3),
+                     (3, 10, 5), (3, 11, 8), (3, 13, 1), (2, 13, 8)];
+        for &(job, worker, cost) in costs.iter() {
+            graph.add_edge(job, worker, cost).unwrap();
+        }
+        graph
+    }
+
+    #[test]
+    fn even_cycle_is_bipartite() {
+        let mut graph = Graph::new_undirected();
+        for id in 0..6 {
+            graph.add_node(id);
+        }
+        for id in 0..6 {
+            graph.add_edge(id, (id + 1) % 6, 1).unwrap();
+        }
+        let parts = graph.is_bipartite().unwrap();
+        for id in 0..6 {
+            assert_ne!(parts.side_of(id), parts.side_of((id + 1) % 6));
+        }
+        assert_eq!(parts.left().len(), 3);
+        assert_eq!(parts.right().len(), 3);
+    }
+
+    #[test]
+    fn odd_cycle_witness() {
+        let mut graph = Graph::new();
+        for id in 0..8 {
+            graph.add_node(id);
+        }
+        // square 0-1-2-3 with a tail 3-4-5-6-7 closing back on 4 through 7 -> 4 makes a 4 cycle,
+        // and a chord 5 -> 7 makes the odd triangle 5, 6, 7
+        for (a, b) in [(0, 1), (1, 2), (2, 3), (3, 0), (3, 4), (4, 5), (5, 6), (6, 7), (7, 4), (5, 7)] {
+            graph.add_edge(a, b, 1).unwrap();
+        }
+        let cycle = graph.is_bipartite().unwrap_err();
+        assert_odd_cycle(&graph, &cycle);
+        assert!(graph.maximum_matching().is_err());
+
+        let mut triangle = Graph::<i32, i32>::new_undirected();
+        for id in 0..3 {
+            triangle.add_node(id);
+        }
+        triangle.add_edge(0, 1, 1).unwrap();
+        triangle.add_edge(1, 2, 1).unwrap();
+        triangle.add_edge(2, 0, 1).unwrap();
+        let cycle = triangle.is_bipartite().unwrap_err();
+        assert_eq!(cycle.nodes().len(), 3);
+        assert_odd_cycle(&triangle, &cycle);
+    }
+
+    #[test]
+    fn self_loop_is_odd() {
+        let mut graph = Graph::<i32, i32>::new();
+        graph.add_node(1);
+        graph.add_edge(1, 1, 1).unwrap();
+        assert_eq!(graph.is_bipartite().unwrap_err().nodes(), &[1]);
+    }
+
+    #[test]
+    fn hopcroft_karp() {
+        let graph = workers();
+        let matching = graph.maximum_matching().unwrap();
+        assert_eq!(matching.len(), 3);
+        let mut used = HashSet::new();
+        for &(a, b) in matching.pairs() {
+            assert!(graph.contains_edge(a, b));
+            assert!(used.insert(a) && used.insert(b));
+            assert_eq!(matching.mate(a), Some(b));
+        }
+    }
+
+    #[test]
+    fn hopcroft_karp_needs_augmenting_paths() {
+        // a chain where a greedy matching of the middle edges blocks a perfect matching
+        let mut graph = Graph::<i32, i32>::new();
+        for id in 0..8 {
+            graph.add_node(id);
+        }
+        for (a, b) in [(0, 4), (1, 4), (1, 5), (2, 5), (2, 6), (3, 6), (3, 7)] {
+            graph.add_edge(a, b, 1).unwrap();
+        }
+        assert_eq!(graph.maximum_matching().unwrap().len(), 4);
+        graph.remove_edge(3, 7).unwrap();
+        assert_eq!(graph.maximum_matching().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn hungarian_assignment() {
+        let graph = workers();
+        let assignment = graph.min_cost_assignment(&[1, 2, 3]).unwrap().unwrap();
+        assert_eq!(assignment.len(), 3);
+        assert_eq!(assignment.total_weight(), 6);
+        assert_eq!(assignment.mate(1), Some(11));
+        assert_eq!(assignment.mate(2), Some(12));
+        assert_eq!(assignment.mate(3), Some(13));
+        assert!(assignment.pairs().iter().all(|&(job, _)| job < 10));
+
+        // naming the bigger side as left gives the same answer
+        let flipped = graph.min_cost_assignment(&[10, 11, 12, 13]).unwrap().unwrap();
+        assert_eq!(flipped.total_weight(), 6);
+        assert_eq!(flipped.mate(13), Some(3));
+        assert!(flipped.pairs().iter().all(|&(worker, _)| worker >= 10));
+    }
+
+    #[test]
+    fn hungarian_prefers_total_over_greedy() {
+        let mut graph = Graph::new_undirected();
+        for id in [1, 2, 10, 11] {
+            graph.add_node(id);
+        }
+        graph.add_edge(1, 10, 1).unwrap();
+        graph.add_edge(1, 11, 2).unwrap();
+        graph.add_edge(2, 10, 2).unwrap();
+        graph.add_edge(2, 11, 100).unwrap();
+        let assignment = graph.min_cost_assignment(&[1, 2]).unwrap().unwrap();
+        assert_eq!(assignment.total_weight(), 4);
+        assert_eq!(assignment.mate(2), Some(10));
+    }
+
+    #[test]
+    fn hungarian_infeasible() {
+        let mut graph = workers();
+        graph.add_node(4);
+        graph.add_node(5);
+        graph.add_edge(4, 13, 1).unwrap();
+        graph.add_edge(5, 13, 1).unwrap();
+        assert!(graph.min_cost_assignment(&[3, 4, 5]).unwrap().is_none());
+        assert!(graph.min_cost_assignment(&[1, 2, 3, 4]).unwrap().is_some());
+        assert_eq!(graph.min_cost_assignment(&[1, 99]).unwrap_err(), GraphError::NodeNotFound(99));
+    }
+
+    #[test]
+    fn hungarian_unsigned_weights() {
+        let mut graph: Graph<u32, u32> = Graph::new_undirected();
+        for id in [1, 2, 10, 11] {
+            graph.add_node(id);
+        }
+        graph.add_edge(1, 10, 4).unwrap();
+        graph.add_edge(1, 11, 1).unwrap();
+        graph.add_edge(2, 10, 2).unwrap();
+        graph.add_edge(2, 11, 3).unwrap();
+        let assignment = graph.min_cost_assignment(&[1, 2]).unwrap().unwrap();
+        assert_eq!(assignment.total_weight(), 3);
+        assert_eq!(assignment.mate(1), Some(11));
+    }
+
+    #[test]
+    fn hopcroft_karp_matches_max_flow() {
+        let mut rng = StdRng::seed_from_u64(12);
+        for _ in 0..20 {
+            // left nodes 0..30, right nodes 100..130, and a unit capacity network through them
+            let mut graph: Graph<u32, u32> = Graph::new_undirected();
+            let mut network: Graph<u32, u32> = Graph::new();
+            for id in (0..30).chain(100..130).chain([1000, 1001]) {
+                graph.add_node(id);
+                network.add_node(id);
+            }
+            for id in 0..30 {
+                network.add_edge(1000, id, 1).unwrap();
+                network.add_edge(id + 100, 1001, 1).unwrap();
+            }
+            for _ in 0..50 {
+                let (a, b) = (rng.gen_range(0..30), rng.gen_range(100..130));
+                if !graph.contains_edge(a, b) {
+                    graph.add_edge(a, b, 1).unwrap();
+                    network.add_edge(a, b, 1).unwrap();
+                }
+            }
+            let matching = graph.maximum_matching().unwrap();
+            assert_eq!(matching.len() as u32, network.max_flow(1000, 1001).unwrap().value());
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::queue::Queue;
+use super::{Graph, GraphError};
+
+/// Settings for Graph::pagerank, built up from PageRankConfig::new()
+#[derive(Clone, Debug)]
+pub struct PageRankConfig<T> {
+    damping: f64,
+    tolerance: f64,
+    max_iterations: usize,
+    personalization: Option<HashMap<T, f64>>
+}
+
+impl<T: Hash+Eq+Copy> PageRankConfig<T> {
+    /// damping of 0.85, tolerance of 1e-6, at most 100 iterations and no personalization
+    pub fn new() -> Self {
+        PageRankConfig {
+            damping: 0.85,
+            tolerance: 1e-6,
+            max_iterations: 100,
+            personalization: None
+        }
+    }
+
+    /// chance of following an edge rather than jumping, between 0 and 1
+    pub fn damping(mut self, damping: f64) -> Self {
+        assert!((0.0..=1.0).contains(&damping), "damping has to be between 0 and 1");
+        self.damping = damping;
+        self
+    }
+
+    /// stop once the scores change by less than this in total between iterations
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Jump to nodes in proportion to these weights instead of uniformly, dangling nodes
+    /// spread their score the same way. Nodes left out get no jumps.
+    /// Weights have to be non-negative with a positive total.
+    pub fn personalization(mut self, weights: HashMap<T, f64>) -> Self {
+        assert!(weights.values().all(|&w| w >= 0.0), "personalization weights can't be negative");
+        assert!(weights.values().sum::<f64>() > 0.0, "personalization weights need a positive total");
+        self.personalization = Some(weights);
+        self
+    }
+}
+
+impl<T: Hash+Eq+Copy> Default for PageRankConfig<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Scores found by Graph::pagerank
+#[derive(Clone, Debug)]
+pub struct PageRank<T> {
+    scores: HashMap<T, f64>,
+    iterations: usize,
+    converged: bool
+}
+
+impl<T: Hash+Eq+Copy> PageRank<T> {
+    /// the score of every node, adding up to 1
+    pub fn scores(&self) -> &HashMap<T, f64> {
+        &self.scores
+    }
+
+    /// returns the score of one node
+    /// returns None if id wasn't in the graph
+    pub fn score(&self, id: T) -> Option<f64> {
+        self.scores.get(&id).copied()
+    }
+
+    /// how many power iterations were run
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    /// checks to see if the scores settled within the tolerance before running out of iterations
+    pub fn converged(&self) -> bool {
+        self.converged
+    }
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy, N, E> Graph<T, W, N, E> {
+    /// Ranks the nodes with PageRank by power iteration. Every edge leaving a node gets an
+    /// equal share of its score, ignoring weights, and undirected edges can be followed
+    /// either way. Dangling nodes with no edges out spread their score like a jump does.
+    /// Returns an error if the personalization names a node that isn't in the graph.
+    pub fn pagerank(&self, config: &PageRankConfig<T>) -> Result<PageRank<T>, GraphError<T>> {
+        let (ids, index) = self.index_nodes();
+        let n = ids.len();
+        let jump = match &config.personalization {
+            Some(weights) => {
+                let mut jump = vec![0.0; n];
+                for (&id, &weight) in weights {
+                    match index.get(&id) {
+                        Some(&i) => jump[i] = weight,
+                        None => return Err(GraphError::NodeNotFound(id))
+                    }
+                }
+                let total: f64 = jump.iter().sum();
+                jump.iter().map(|w| w / total).collect()
+            },
+            None => vec![1.0 / n as f64; n]
+        };
+        let adjacency = self.dense_adjacency(&index);
+
+        let mut scores = jump.clone();
+        let mut iterations = 0;
+        let mut converged = n == 0;
+        while !converged && iterations < config.max_iterations {
+            iterations += 1;
+            let dangling: f64 = (0..n).filter(|&i| adjacency[i].is_empty()).map(|i| scores[i]).sum();
+            let mut next: Vec<f64> = jump.iter()
+                .map(|j| (1.0 - config.damping) * j + config.damping * dangling * j)
+                .collect();
+            for (from, targets) in adjacency.iter().enumerate() {
+                if targets.is_empty() {
+                    continue;
+                }
+                let share = config.damping * scores[from] / targets.len() as f64;
+                for &to in targets {
+                    next[to] += share;
+                }
+            }
+            let change: f64 = next.iter().zip(scores.iter()).map(|(a, b)| (a - b).abs()).sum();
+            scores = next;
+            converged = change < config.tolerance;
+        }
+        Ok(PageRank {
+            scores: ids.into_iter().zip(scores).collect(),
+            iterations,
+            converged
+        })
+    }
+
+    /// The fraction of the other nodes each node shares an edge with, edges in and out both
+    /// count for directed graphs, so scores can go above 1 with parallel edges or both directions
+    pub fn degree_centrality(&self) -> HashMap<T, f64> {
+        let mut degrees: HashMap<T, usize> = self.nodes.keys().map(|&id| (id, 0)).collect();
+        for edge in self.edges.iter().flatten() {
+            *degrees.get_mut(&edge.node_from).unwrap() += 1;
+            *degrees.get_mut(&edge.node_to).unwrap() += 1;
+        }
+        let others = self.nodes.len().saturating_sub(1).max(1) as f64;
+        degrees.into_iter().map(|(id, degree)| (id, degree as f64 / others)).collect()
+    }
+
+    /// Closeness centrality from hop counts along edges leaving each node, ignoring weights.
+    /// A node reaching r other nodes at a total distance of d scores (r / d) * (r / (n - 1)),
+    /// which scales the score down for nodes that can't reach the whole graph.
+    pub fn closeness_centrality(&self) -> HashMap<T, f64> {
+        let (ids, index) = self.index_nodes();
+        let n = ids.len();
+        let adjacency = self.dense_adjacency(&index);
+        let mut distance = vec![None; n];
+        let mut scores = HashMap::with_capacity(n);
+        for (source, &id) in ids.iter().enumerate() {
+            let mut total = 0;
+            let mut reached = 0;
+            for (node, dist) in hop_order(&adjacency, source, &mut distance) {
+                if node != source {
+                    total += dist;
+                    reached += 1;
+                }
+            }
+            let score = if total == 0 {
+                0.0
+            } else {
+                let reached = reached as f64;
+                (reached / total as f64) * (reached / (n - 1) as f64)
+            };
+            scores.insert(id, score);
+        }
+        scores
+    }
+
+    /// Betweenness centrality by Brandes' algorithm over hop counts, ignoring weights.
+    /// A node scores the fraction of shortest paths through it summed over every pair of
+    /// other nodes, counting each unordered pair once in undirected graphs. Not normalized.
+    pub fn betweenness_centrality(&self) -> HashMap<T, f64> {
+        let (ids, index) = self.index_nodes();
+        let n = ids.len();
+        let adjacency = self.dense_adjacency(&index);
+        let mut centrality = vec![0.0; n];
+        let mut distance = vec![None; n];
+        let mut paths = vec![0.0; n];
+        let mut dependency = vec![0.0; n];
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n];
+        for source in 0..n {
+            for i in 0..n {
+                paths[i] = 0.0;
+                dependency[i] = 0.0;
+                predecessors[i].clear();
+            }
+            paths[source] = 1.0;
+            // nodes in the order the breadth first search settled them
+            let order = hop_order(&adjacency, source, &mut distance);
+            for &(node, dist) in &order {
+                for &next in &adjacency[node] {
+                    if distance[next] == Some(dist + 1) {
+                        paths[next] += paths[node];
+                        predecessors[next].push(node);
+                    }
+                }
+            }
+            for &(node, _) in order.iter().rev() {
+                for &prev in &predecessors[node] {
+                    dependency[prev] += paths[prev] / paths[node] * (1.0 + dependency[node]);
+                }
+                if node != source {
+                    centrality[node] += dependency[node];
+                }
+            }
+        }
+        // every undirected path was counted from both ends
+        let scale = if self.is_directed() { 1.0 } else { 0.5 };
+        ids.into_iter().zip(centrality).map(|(id, score)| (id, score * scale)).collect()
+    }
+
+    // the targets of the edges leaving every node, by dense index
+    pub(super) fn dense_adjacency(&self, index: &HashMap<T, usize>) -> Vec<Vec<usize>> {
+        let mut adjacency = vec![Vec::new(); index.len()];
+        for edge in self.oriented_edges() {
+            adjacency[index[&edge.node_from]].push(index[&edge.node_to]);
+        }
+        adjacency
+    }
+}
+
+// breadth first search from source returning (node, hops) in visiting order, distance is
+// scratch space of one entry per node and holds the hop counts afterwards
+fn hop_order(adjacency: &[Vec<usize>], source: usize, distance: &mut [Option<usize>]) -> Vec<(usize, usize)> {
+    for dist in distance.iter_mut() {
+        *dist = None;
+    }
+    let mut order = Vec::new();
+    let mut queue = Queue::new();
+    distance[source] = Some(0);
+    queue.push(source);
+    while let Some(node) = queue.pop() {
+        let dist = distance[node].unwrap();
+        order.push((node, dist));
+        for &next in &adjacency[node] {
+            if distance[next].is_none() {
+                distance[next] = Some(dist + 1);
+                queue.push(next);
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Direction;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    // 1 -> 2, 1 -> 3, 2 -> 3, 3 -> 1, 4 -> 3, 5 has no edges out
+    fn init() -> Graph<i32, i32> {
+        let mut graph = Graph::new();
+        for id in 1..=5 {
+            graph.add_node(id);
+        }
+        graph.add_edge(1, 2, 1).unwrap();
+        graph.add_edge(1, 3, 1).unwrap();
+        graph.add_edge(2, 3, 1).unwrap();
+        graph.add_edge(3, 1, 1).unwrap();
+        graph.add_edge(4, 3, 1).unwrap();
+        graph.add_edge(4, 5, 1).unwrap();
+        graph
+    }
+
+    #[test]
+    fn pagerank_sums_to_one() {
+        let graph = init();
+        let rank = graph.pagerank(&PageRankConfig::new()).unwrap();
+        assert!(rank.converged());
+        assert!(rank.iterations() > 1 && rank.iterations() < 100);
+        assert!(close(rank.scores().values().sum::<f64>(), 1.0));
+        let best = graph.nodes().max_by(|&a, &b| rank.score(a).partial_cmp(&rank.score(b)).unwrap()).unwrap();
+        assert_eq!(best, 3);
+        assert!(rank.score(4).unwrap() < rank.score(1).unwrap());
+        assert_eq!(rank.score(9), None);
+    }
+
+    #[test]
+    fn pagerank_of_a_cycle_is_uniform() {
+        let graph = Graph::<usize, u32>::cycle(4, Direction::Directed, 1);
+        let rank = graph.pagerank(&PageRankConfig::new().damping(0.9)).unwrap();
+        for id in 0..4 {
+            assert!(close(rank.score(id).unwrap(), 0.25));
+        }
+        assert_eq!(rank.iterations(), 1);
+    }
+
+    #[test]
+    fn pagerank_matches_hand_computation() {
+        // two nodes pointing at each other plus a dangling third, solved by hand:
+        // a = b = (1 - d) / 3 + d * (c / 3) + d * a, c = (1 - d) / 3 + d * (c / 3)
+        let mut graph = Graph::new();
+        for id in 0..3 {
+            graph.add_node(id);
+        }
+        graph.add_edge(0, 1, 1).unwrap();
+        graph.add_edge(1, 0, 1).unwrap();
+        let rank = graph.pagerank(&PageRankConfig::new().damping(0.5).tolerance(1e-12)).unwrap();
+        let c = (0.5 / 3.0) / (1.0 - 0.5 / 3.0);
+        assert!(close(rank.score(2).unwrap(), c));
+        assert!(close(rank.score(0).unwrap(), (1.0 - c) / 2.0));
+    }
+
+    #[test]
+    fn pagerank_personalization_and_limits() {
+        let graph = init();
+        let mut weights = HashMap::new();
+        weights.insert(4, 1.0);
+        let rank = graph.pagerank(&PageRankConfig::new().personalization(weights)).unwrap();
+        let plain = graph.pagerank(&PageRankConfig::new()).unwrap();
+        assert!(rank.score(4).unwrap() > plain.score(4).unwrap());
+        assert!(close(rank.scores().values().sum::<f64>(), 1.0));
+
+        let capped = graph.pagerank(&PageRankConfig::new().max_iterations(2).tolerance(0.0)).unwrap();
+        assert_eq!(capped.iterations(), 2);
+        assert!(!capped.converged());
+
+        let mut unknown = HashMap::new();
+        unknown.insert(9, 1.0);
+        assert_eq!(graph.pagerank(&PageRankConfig::new().personalization(unknown)).err(), Some(GraphError::NodeNotFound(9)));
+    }
+
+    #[test]
+    fn degree_centrality() {
+        let graph = Graph::<usize, u32>::star(5, Direction::Undirected, 1);
+        let scores = graph.degree_centrality();
+        assert!(close(scores[&0], 1.0));
+        assert!(close(scores[&3], 0.25));
+        let scores = init().degree_centrality();
+        assert!(close(scores[&3], 1.0));
+        assert!(close(scores[&5], 0.25));
+    }
+
+    #[test]
+    fn closeness_centrality() {
+        let graph = Graph::<usize, u32>::path(5, Direction::Undirected, 1);
+        let scores = graph.closeness_centrality();
+        assert!(close(scores[&2], 4.0 / 6.0));
+        assert!(close(scores[&0], 4.0 / 10.0));
+        let scores = Graph::<usize, u32>::path(3, Direction::Directed, 1).closeness_centrality();
+        // node 1 only reaches node 2, one of the two others, at distance 1
+        assert!(close(scores[&1], 0.5));
+        assert!(close(scores[&2], 0.0));
+    }
+
+    #[test]
+    fn betweenness_centrality() {
+        let graph = Graph::<usize, u32>::path(5, Direction::Undirected, 1);
+        let scores = graph.betweenness_centrality();
+        let expected = [0.0, 3.0, 4.0, 3.0, 0.0];
+        for (id, &score) in expected.iter().enumerate() {
+            assert!(close(scores[&id], score));
+        }
+        // two equally short paths from 0 to 3 split the credit
+        let mut graph = Graph::new_undirected();
+        for id in 0..4 {
+            graph.add_node(id);
+        }
+        graph.add_edge(0, 1, 1).unwrap();
+        graph.add_edge(0, 2, 1).unwrap();
+        graph.add_edge(1, 3, 1).unwrap();
+        graph.add_edge(2, 3, 1).unwrap();
+        let scores = graph.betweenness_centrality();
+        assert!(scores.values().all(|&score| close(score, 0.5)));
+        let star = Graph::<usize, u32>::star(4, Direction::Directed, 1).betweenness_centrality();
+        assert!(star.values().all(|&score| close(score, 0.0)));
+    }
+}
@@ -0,0 +1,348 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::heap::Heap;
+use super::{Direction, Graph, GraphError};
+
+/// The order greedy coloring visits the nodes in, each node takes the smallest color
+/// none of its already colored neighbors have
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorOrder {
+    /// ascending node id
+    Natural,
+    /// highest degree first, usually fewer colors than natural order
+    LargestFirst,
+    /// repeatedly set aside a node of lowest degree among those left and color them in reverse,
+    /// which colors planar graphs with at most 6 colors
+    SmallestLast
+}
+
+/// A color from 0 to color_count() - 1 for every node, with no edge joining two nodes of
+/// the same color
+#[derive(Clone, Debug)]
+pub struct Coloring<T> {
+    colors: HashMap<T, usize>,
+    classes: Vec<Vec<T>>
+}
+
+impl<T: Hash+Eq+Copy> Coloring<T> {
+    fn from_colors(colors: HashMap<T, usize>) -> Self {
+        let count = colors.values().map(|&color| color + 1).max().unwrap_or(0);
+        let mut classes = vec![Vec::new(); count];
+        for (&id, &color) in colors.iter() {
+            classes[color].push(id);
+        }
+        Coloring {
+            colors,
+            classes
+        }
+    }
+
+    /// returns the number of colors used
+    pub fn color_count(&self) -> usize {
+        self.classes.len()
+    }
+
+    /// returns the color given to id
+    /// returns None if id wasn't in the graph
+    pub fn color_of(&self, id: T) -> Option<usize> {
+        self.colors.get(&id).copied()
+    }
+
+    /// the color of every node
+    pub fn colors(&self) -> &HashMap<T, usize> {
+        &self.colors
+    }
+
+    /// the nodes of each color, indexed by color, in no particular order
+    pub fn classes(&self) -> &[Vec<T>] {
+        &self.classes
+    }
+}
+
+/// Reasons a coloring handed to check_coloring isn't a proper coloring of the graph
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ColoringError<T> {
+    /// the node is in the graph but has no color
+    Uncolored(T),
+    /// an edge joins two nodes of the same color, (from, to)
+    Conflict(T, T)
+}
+
+impl<T: fmt::Debug> fmt::Display for ColoringError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColoringError::Uncolored(id) => write!(f, "node {:?} has no color", id),
+            ColoringError::Conflict(from, to) => write!(f, "nodes {:?} and {:?} share an edge and a color", from, to)
+        }
+    }
+}
+
+impl<T: fmt::Debug> Error for ColoringError<T> {}
+
+/* Colorings ignore self loops, which no coloring could satisfy, and give ties to the
+smaller node id so the same graph always gets the same colors. */
+impl<T: Hash+Eq+Copy+Ord, W: Ord+Copy, N, E> Graph<T, W, N, E> {
+    /// Colors the nodes greedily, visiting them in the given order
+    /// Returns an error if the graph is directed
+    pub fn greedy_coloring(&self, order: ColorOrder) -> Result<Coloring<T>, GraphError<T>> {
+        self.require_direction(Direction::Undirected)?;
+        let (ids, neighbors) = self.coloring_adjacency();
+        let visit: Vec<usize> = match order {
+            ColorOrder::Natural => (0..ids.len()).collect(),
+            ColorOrder::LargestFirst => {
+                let mut visit: Vec<usize> = (0..ids.len()).collect();
+                // stable, so equal degrees stay in id order
+                visit.sort_by_key(|&node| Reverse(neighbors[node].len()));
+                visit
+            },
+            ColorOrder::SmallestLast => smallest_last(&neighbors)
+        };
+
+        let mut colors = vec![None; ids.len()];
+        // taken_by[c] == node marks color c as used by one of node's neighbors
+        let mut taken_by = vec![usize::MAX; ids.len()];
+        for node in visit {
+            for &neighbor in &neighbors[node] {
+                if let Some(color) = colors[neighbor] {
+                    taken_by[color] = node;
+                }
+            }
+            colors[node] = (0..).find(|&color| taken_by[color] != node);
+        }
+        Ok(Self::collect_colors(&ids, &colors))
+    }
+
+    /// Colors the nodes with Brélaz's DSatur heuristic. The next node colored is the one whose
+    /// neighbors already have the most different colors, then the one with the highest degree.
+    /// Returns an error if the graph is directed
+    pub fn dsatur_coloring(&self) -> Result<Coloring<T>, GraphError<T>> {
+        self.require_direction(Direction::Undirected)?;
+        let (ids, neighbors) = self.coloring_adjacency();
+        let n = ids.len();
+        let mut colors: Vec<Option<usize>> = vec![None; n];
+        // neighbor_colors[node][c] is true once a neighbor of node has color c
+        let mut neighbor_colors = vec![Vec::new(); n];
+        let mut saturation = vec![0; n];
+        // (saturation, degree, node) with the smaller node winning ties, entries go stale
+        // when a node's saturation grows and are skipped
+        let mut candidates = Heap::new();
+        for (node, targets) in neighbors.iter().enumerate() {
+            candidates.push((0, targets.len(), Reverse(node)));
+        }
+
+        while let Some((node_saturation, _, Reverse(node))) = candidates.pop() {
+            if colors[node].is_some() || node_saturation != saturation[node] {
+                continue;
+            }
+            let seen = &neighbor_colors[node];
+            let color = (0..).find(|&color| !seen.get(color).copied().unwrap_or(false)).expect("Some color is always free");
+            colors[node] = Some(color);
+            for &neighbor in &neighbors[node] {
+                if colors[neighbor].is_some() {
+                    continue;
+                }
+                let seen = &mut neighbor_colors[neighbor];
+                if seen.len() <= color {
+                    seen.resize(color + 1, false);
+                }
+                if !seen[color] {
+                    seen[color] = true;
+                    saturation[neighbor] += 1;
+                    candidates.push((saturation[neighbor], neighbors[neighbor].len(), Reverse(neighbor)));
+                }
+            }
+        }
+        Ok(Self::collect_colors(&ids, &colors))
+    }
+
+    /// Checks that colors gives every node a color and that no edge joins two nodes of the
+    /// same color. Directed edges are checked the same way as undirected ones, self loops
+    /// are ignored and extra entries for nodes not in the graph are allowed.
+    pub fn check_coloring(&self, colors: &HashMap<T, usize>) -> Result<(), ColoringError<T>> {
+        for id in self.nodes.keys() {
+            if !colors.contains_key(id) {
+                return Err(ColoringError::Uncolored(*id));
+            }
+        }
+        for edge in self.edges.iter().flatten() {
+            if edge.node_from != edge.node_to && colors[&edge.node_from] == colors[&edge.node_to] {
+                return Err(ColoringError::Conflict(edge.node_from, edge.node_to));
+            }
+        }
+        Ok(())
+    }
+
+    // ids in ascending order and the distinct neighbors of each by index, without self loops
+    fn coloring_adjacency(&self) -> (Vec<T>, Vec<Vec<usize>>) {
+        let mut ids: Vec<T> = self.nodes.keys().copied().collect();
+        ids.sort_unstable();
+        let index: HashMap<T, usize> = ids.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+        let mut neighbors = self.dense_adjacency(&index);
+        for (node, targets) in neighbors.iter_mut().enumerate() {
+            targets.retain(|&target| target != node);
+            targets.sort_unstable();
+            targets.dedup();
+        }
+        (ids, neighbors)
+    }
+
+    fn collect_colors(ids: &[T], colors: &[Option<usize>]) -> Coloring<T> {
+        let colors = ids.iter().zip(colors).map(|(&id, color)| {
+            (id, color.expect("Every node is colored"))
+        }).collect();
+        Coloring::from_colors(colors)
+    }
+}
+
+// the smallest last order, built by taking out a node of lowest remaining degree until none
+// are left and reversing. Buckets hold nodes by remaining degree and keep stale entries.
+fn smallest_last(neighbors: &[Vec<usize>]) -> Vec<usize> {
+    let n = neighbors.len();
+    let mut degree: Vec<usize> = neighbors.iter().map(|targets| targets.len()).collect();
+    let mut buckets = vec![Vec::new(); n];
+    // filled from the back so the smallest node id comes off a bucket first
+    for node in (0..n).rev() {
+        buckets[degree[node]].push(node);
+    }
+    let mut removed = vec![false; n];
+    let mut order = Vec::with_capacity(n);
+    let mut lowest = 0;
+    while order.len() < n {
+        let node = match buckets[lowest].pop() {
+            Some(node) if removed[node] || degree[node] != lowest => continue,
+            Some(node) => node,
+            None => {
+                lowest += 1;
+                continue;
+            }
+        };
+        removed[node] = true;
+        order.push(node);
+        for &neighbor in &neighbors[node] {
+            if !removed[neighbor] {
+                degree[neighbor] -= 1;
+                buckets[degree[neighbor]].push(neighbor);
+            }
+        }
+        // a neighbor may have dropped one below the current bucket
+        lowest = lowest.saturating_sub(1);
+    }
+    order.reverse();
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    const ORDERS: [ColorOrder; 3] = [ColorOrder::Natural, ColorOrder::LargestFirst, ColorOrder::SmallestLast];
+
+    // the crown graph on 2n nodes, even node 2i joined to odd node 2j + 1 whenever i != j.
+    // It's bipartite, but natural order colors it with n colors.
+    fn crown(n: usize) -> Graph<usize, u32> {
+        let mut graph = Graph::new_undirected();
+        for id in 0..2 * n {
+            graph.add_node(id);
+        }
+        for i in 0..n {
+            for j in 0..n {
+                if i != j {
+                    graph.add_edge(2 * i, 2 * j + 1, 1).unwrap();
+                }
+            }
+        }
+        graph
+    }
+
+    #[test]
+    fn colorings_are_proper() {
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..5 {
+            let graph = Graph::<usize, u32>::gnp(60, 0.15, Direction::Undirected, 1, &mut rng);
+            for &order in ORDERS.iter() {
+                let coloring = graph.greedy_coloring(order).unwrap();
+                assert_eq!(graph.check_coloring(coloring.colors()), Ok(()));
+                assert_eq!(coloring.classes().iter().map(|class| class.len()).sum::<usize>(), 60);
+            }
+            let coloring = graph.dsatur_coloring().unwrap();
+            assert_eq!(graph.check_coloring(coloring.colors()), Ok(()));
+        }
+    }
+
+    #[test]
+    fn known_chromatic_numbers() {
+        let complete = Graph::<usize, u32>::complete(7, Direction::Undirected, 1);
+        let odd_cycle = Graph::<usize, u32>::cycle(9, Direction::Undirected, 1);
+        let grid = Graph::<usize, u32>::grid(5, 6, Direction::Undirected, 1);
+        let star = Graph::<usize, u32>::star(10, Direction::Undirected, 1);
+        for (graph, colors) in [(&complete, 7), (&odd_cycle, 3), (&grid, 2), (&star, 2)] {
+            assert_eq!(graph.dsatur_coloring().unwrap().color_count(), colors);
+        }
+        for (graph, colors) in [(&complete, 7), (&odd_cycle, 3), (&star, 2)] {
+            assert_eq!(graph.greedy_coloring(ColorOrder::SmallestLast).unwrap().color_count(), colors);
+        }
+    }
+
+    #[test]
+    fn ordering_matters() {
+        let graph = crown(5);
+        assert_eq!(graph.greedy_coloring(ColorOrder::Natural).unwrap().color_count(), 5);
+        assert_eq!(graph.dsatur_coloring().unwrap().color_count(), 2);
+    }
+
+    #[test]
+    fn colors_and_classes_agree() {
+        let graph = crown(4);
+        let coloring = graph.greedy_coloring(ColorOrder::LargestFirst).unwrap();
+        for (color, class) in coloring.classes().iter().enumerate() {
+            for &id in class {
+                assert_eq!(coloring.color_of(id), Some(color));
+            }
+        }
+        assert_eq!(coloring.color_of(100), None);
+    }
+
+    #[test]
+    fn self_loops_are_ignored() {
+        let mut graph = Graph::<usize, u32>::path(3, Direction::Undirected, 1);
+        graph.add_edge(1, 1, 1).unwrap();
+        let coloring = graph.dsatur_coloring().unwrap();
+        assert_eq!(coloring.color_count(), 2);
+        assert_eq!(graph.check_coloring(coloring.colors()), Ok(()));
+    }
+
+    #[test]
+    fn check_coloring_reports_problems() {
+        let graph = Graph::<usize, u32>::path(3, Direction::Undirected, 1);
+        let mut colors: HashMap<usize, usize> = [(0, 0), (1, 1)].iter().copied().collect();
+        assert_eq!(graph.check_coloring(&colors), Err(ColoringError::Uncolored(2)));
+        colors.insert(2, 1);
+        let err = graph.check_coloring(&colors).unwrap_err();
+        assert_eq!(err, ColoringError::Conflict(1, 2));
+        assert_eq!(err.to_string(), "nodes 1 and 2 share an edge and a color");
+        colors.insert(2, 0);
+        colors.insert(7, 0);
+        assert_eq!(graph.check_coloring(&colors), Ok(()));
+    }
+
+    #[test]
+    fn directed_graphs_are_rejected() {
+        let graph = Graph::<usize, u32>::path(3, Direction::Directed, 1);
+        assert_eq!(graph.dsatur_coloring().err(), Some(GraphError::UnsupportedDirection(Direction::Directed)));
+        assert!(graph.greedy_coloring(ColorOrder::Natural).is_err());
+    }
+
+    #[test]
+    fn large_sparse_graph() {
+        let graph = Graph::<usize, u32>::grid(300, 300, Direction::Undirected, 1);
+        // every subgraph of a grid has a node of degree 2 or less
+        assert!(graph.greedy_coloring(ColorOrder::SmallestLast).unwrap().color_count() <= 3);
+        assert_eq!(graph.dsatur_coloring().unwrap().color_count(), 2);
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::union_find::UnionFind;
+use super::{Direction, Graph, GraphError};
+
+/// Partition of a graph's nodes into components, each labeled with an id from 0 to count() - 1
+#[derive(Clone, Debug)]
+pub struct Components<T> {
+    labels: HashMap<T, usize>,
+    members: Vec<Vec<T>>
+}
+
+impl<T: Hash+Eq+Copy> Components<T> {
+    fn from_members(members: Vec<Vec<T>>) -> Self {
+        let mut labels = HashMap::new();
+        for (label, component) in members.iter().enumerate() {
+            for &id in component {
+                labels.insert(id, label);
+            }
+        }
+        Components {
+            labels,
+            members
+        }
+    }
+
+    /// returns the number of components
+    pub fn count(&self) -> usize {
+        self.members.len()
+    }
+
+    /// returns the label of the component holding id
+    /// returns None if id wasn't in the graph
+    pub fn component_of(&self, id: T) -> Option<usize> {
+        self.labels.get(&id).copied()
+    }
+
+    /// checks to see if both nodes landed in the same component
+    pub fn same_component(&self, a: T, b: T) -> bool {
+        match (self.component_of(a), self.component_of(b)) {
+            (Some(x), Some(y)) => x == y,
+            _ => false
+        }
+    }
+
+    /// the component label of every node
+    pub fn labels(&self) -> &HashMap<T, usize> {
+        &self.labels
+    }
+
+    /// the nodes in each component, indexed by label
+    pub fn components(&self) -> &[Vec<T>] {
+        &self.members
+    }
+
+    /// returns the nodes in one component
+    /// returns None if there is no component with that label
+    pub fn members(&self, label: usize) -> Option<&[T]> {
+        self.members.get(label).map(|m| &m[..])
+    }
+
+    /// the number of nodes in each component, indexed by label
+    pub fn sizes(&self) -> Vec<usize> {
+        self.members.iter().map(|m| m.len()).collect()
+    }
+}
+
+// bookkeeping for a node during Tarjan's algorithm
+struct TarjanState {
+    index: usize,
+    low_link: usize,
+    on_stack: bool
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy, N, E> Graph<T, W, N, E> {
+    /// Finds the strongly connected components with an iterative version of Tarjan's algorithm.
+    /// Labels are in topological order of the condensation, so every edge between two
+    /// components goes from a lower label to a higher one.
+    pub fn strongly_connected_components(&self) -> Components<T> {
+        let mut state: HashMap<T, TarjanState> = HashMap::with_capacity(self.nodes.len());
+        let mut next_index = 0;
+        let mut component_stack = Vec::new();
+        let mut members = Vec::new();
+        // (node, index of the next edge to look at) standing in for the recursive calls
+        let mut calls: Vec<(T, usize)> = Vec::new();
+
+        for &root in self.nodes.keys() {
+            if state.contains_key(&root) {
+                continue;
+            }
+            state.insert(root, TarjanState { index: next_index, low_link: next_index, on_stack: true });
+            next_index += 1;
+            component_stack.push(root);
+            calls.push((root, 0));
+
+            while let Some(&(node, next_edge)) = calls.last() {
+                let slots = self.edge_slots(node);
+                if next_edge < slots.len() {
+                    calls.last_mut().unwrap().1 += 1;
+                    let child = self.edge_at(node, slots[next_edge]).node_to;
+                    match state.get(&child) {
+                        None => {
+                            state.insert(child, TarjanState { index: next_index, low_link: next_index, on_stack: true });
+                            next_index += 1;
+                            component_stack.push(child);
+                            calls.push((child, 0));
+                        },
+                        Some(child_state) if child_state.on_stack => {
+                            let child_index = child_state.index;
+                            let node_state = state.get_mut(&node).unwrap();
+                            node_state.low_link = node_state.low_link.min(child_index);
+                        },
+                        Some(_) => {}
+                    }
+                } else {
+                    calls.pop();
+                    let (index, low_link) = {
+                        let node_state = &state[&node];
+                        (node_state.index, node_state.low_link)
+                    };
+                    if index == low_link {
+                        let mut component = Vec::new();
+                        while let Some(member) = component_stack.pop() {
+                            state.get_mut(&member).unwrap().on_stack = false;
+                            component.push(member);
+                            if member == node {
+                                break;
+                            }
+                        }
+                        members.push(component);
+                    }
+                    if let Some(&(parent, _)) = calls.last() {
+                        let parent_state = state.get_mut(&parent).unwrap();
+                        parent_state.low_link = parent_state.low_link.min(low_link);
+                    }
+                }
+            }
+        }
+
+        // Tarjan finishes sink components first
+        members.reverse();
+        Components::from_members(members)
+    }
+
+    /// Finds the connected components of an undirected graph by joining the ends of every
+    /// edge in a UnionFind, which takes close to linear time and no recursion
+    /// Returns an error if the graph is directed
+    pub fn connected_components(&self) -> Result<Components<T>, GraphError<T>> {
+        self.require_direction(Direction::Undirected)?;
+        Ok(self.weakly_connected_components())
+    }
+
+    /// Finds the weakly connected components, the groups of nodes that are connected
+    /// when edge direction is ignored. Undirected graphs get their connected components.
+    pub fn weakly_connected_components(&self) -> Components<T> {
+        let (ids, index) = self.index_nodes();
+        let mut sets = UnionFind::new(ids.len());
+        for edge in self.edges.iter().flatten() {
+            sets.union(index[&edge.node_from], index[&edge.node_to]);
+        }
+        let mut root_labels = HashMap::with_capacity(sets.set_count());
+        let mut members: Vec<Vec<T>> = Vec::with_capacity(sets.set_count());
+        for (i, &id) in ids.iter().enumerate() {
+            let root = sets.find(i);
+            let label = *root_labels.entry(root).or_insert_with(|| {
+                members.push(Vec::with_capacity(sets.set_size(root)));
+                members.len() - 1
+            });
+            members[label].push(id);
+        }
+        Components::from_members(members)
+    }
+
+    /// Collects every node in the same weakly connected component as id, id included, with
+    /// one walk out from id that ignores edge direction. Directed graphs without the reverse
+    /// index build a neighbor list for the whole graph first, since they can't find the
+    /// edges coming into a node without scanning every edge.
+    /// Returns an error if id is not a node in the graph
+    pub fn component_of(&self, id: T) -> Result<Vec<T>, GraphError<T>> {
+        self.check_node(id)?;
+        let neighbors = if self.is_directed() && !self.has_reverse_index() { Some(self.undirected_neighbors()) } else { None };
+        let mut visited = HashSet::new();
+        let mut stack = vec![id];
+        let mut found = Vec::new();
+        visited.insert(id);
+        while let Some(node) = stack.pop() {
+            found.push(node);
+            match &neighbors {
+                Some(neighbors) => push_unvisited(neighbors[&node].iter().copied(), &mut visited, &mut stack),
+                None => {
+                    push_unvisited(self.out_edges(node).map(|edge| edge.node_to), &mut visited, &mut stack);
+                    if self.is_directed() {
+                        push_unvisited(self.in_edges(node).map(|edge| edge.node_from), &mut visited, &mut stack);
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// Collapses every strongly connected component into a single node.
+    /// Nodes of the new graph are the component labels, and every edge between two
+    /// different components is kept with its weight, so the result is an acyclic
+    /// directed multigraph. The components are returned alongside to map nodes to labels.
+    pub fn condensation(&self) -> (Graph<usize, W>, Components<T>) {
+        let components = self.strongly_connected_components();
+        let mut condensed = Graph::new();
+        for label in 0..components.count() {
+            condensed.add_node(label);
+        }
+        for edge in self.oriented_edges() {
+            let from = components.labels[&edge.node_from];
+            let to = components.labels[&edge.node_to];
+            if from != to && condensed.add_edge(from, to, edge.weight).is_err() {
+                unreachable!("Every component label was added as a node");
+            }
+        }
+        (condensed, components)
+    }
+}
+
+// queues the nodes of next that haven't been seen yet
+fn push_unvisited<T: Hash+Eq+Copy, I: Iterator<Item=T>>(next: I, visited: &mut HashSet<T>, stack: &mut Vec<T>) {
+    for node in next {
+        if visited.insert(node) {
+            stack.push(node);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // {1, 2, 3} -> {4, 5} -> {6}, {7} -> {6}
+    fn init() -> Graph<i32, i32> {
+        let mut graph = Graph::new();
+        for id in 1..=7 {
+            graph.add_node(id);
+        }
+        graph.add_edge(1, 2, 1).unwrap();
+        graph.add_edge(2, 3, 1).unwrap();
+        graph.add_edge(3, 1, 1).unwrap();
+        graph.add_edge(3, 4, 2).unwrap();
+        graph.add_edge(2, 5, 3).unwrap();
+        graph.add_edge(4, 5, 1).unwrap();
+        graph.add_edge(5, 4, 1).unwrap();
+        graph.add_edge(5, 6, 4).unwrap();
+        graph.add_edge(7, 6, 5).unwrap();
+        graph
+    }
+
+    fn sorted(members: &[i32]) -> Vec<i32> {
+        let mut members = members.to_vec();
+        members.sort();
+        members
+    }
+
+    #[test]
+    fn components_found() {
+        let graph = init();
+        let components = graph.strongly_connected_components();
+        assert_eq!(components.count(), 4);
+        assert!(components.same_component(1, 3));
+        assert!(components.same_component(4, 5));
+        assert!(!components.same_component(3, 4));
+        assert!(!components.same_component(6, 7));
+        let label = components.component_of(2).unwrap();
+        assert_eq!(sorted(components.members(label).unwrap()), vec![1, 2, 3]);
+        assert_eq!(components.component_of(8), None);
+        let mut sizes = components.sizes();
+        sizes.sort();
+        assert_eq!(sizes, vec![1, 1, 2, 3]);
+    }
+
+    #[test]
+    fn labels_are_topologically_ordered() {
+        let graph = init();
+        let components = graph.strongly_connected_components();
+        for edge in graph.oriented_edges() {
+            assert!(components.component_of(edge.node_from) <= components.component_of(edge.node_to));
+        }
+    }
+
+    #[test]
+    fn weakly_connected() {
+        let mut graph = init();
+        graph.add_node(8);
+        graph.add_node(9);
+        graph.add_edge(9, 8, 1).unwrap();
+        let components = graph.weakly_connected_components();
+        assert_eq!(components.count(), 2);
+        assert!(components.same_component(1, 7));
+        assert!(components.same_component(8, 9));
+        assert!(!components.same_component(7, 8));
+        let mut sizes = components.sizes();
+        sizes.sort();
+        assert_eq!(sizes, vec![2, 7]);
+        assert_eq!(sorted(&graph.component_of(8).unwrap()), vec![8, 9]);
+        assert_eq!(sorted(&graph.component_of(6).unwrap()), (1..=7).collect::<Vec<_>>());
+        assert_eq!(graph.component_of(10), Err(GraphError::NodeNotFound(10)));
+        graph.enable_reverse_index();
+        assert_eq!(sorted(&graph.component_of(8).unwrap()), vec![8, 9]);
+        assert_eq!(sorted(&graph.component_of(6).unwrap()), (1..=7).collect::<Vec<_>>());
+        assert_eq!(graph.connected_components().err(), Some(GraphError::UnsupportedDirection(Direction::Directed)));
+    }
+
+    #[test]
+    fn connected_undirected() {
+        let mut graph = Graph::<usize, u32>::grid(3, 3, Direction::Undirected, 1);
+        graph.add_node(9);
+        graph.add_node(10);
+        graph.add_edge(10, 10, 1).unwrap();
+        let components = graph.connected_components().unwrap();
+        assert_eq!(components.count(), 3);
+        assert_eq!(components.component_of(0), components.component_of(8));
+        assert_ne!(components.component_of(9), components.component_of(10));
+        assert_eq!(components.component_of(11), None);
+        assert_eq!(graph.component_of(4).unwrap().len(), 9);
+        assert_eq!(graph.component_of(10), Ok(vec![10]));
+    }
+
+    #[test]
+    fn many_components() {
+        let mut graph = Graph::new_undirected();
+        for id in 0..200_000 {
+            graph.add_node(id);
+            if id % 4 != 0 {
+                graph.add_edge(id - 1, id, 1).unwrap();
+            }
+        }
+        let components = graph.connected_components().unwrap();
+        assert_eq!(components.count(), 50_000);
+        assert!(components.sizes().iter().all(|&size| size == 4));
+    }
+
+    #[test]
+    fn condensation_is_acyclic() {
+        let graph = init();
+        let (condensed, components) = graph.condensation();
+        assert!(condensed.is_acyclic());
+        let big = components.component_of(1).unwrap();
+        let pair = components.component_of(4).unwrap();
+        let sink = components.component_of(6).unwrap();
+        let mut out = condensed.get_edges_from(big).unwrap();
+        out.sort();
+        assert_eq!(out, vec![(pair, 2), (pair, 3)]);
+        assert_eq!(condensed.get_edges_from(pair), Some(vec![(sink, 4)]));
+        assert_eq!(condensed.get_edges_from(sink), Some(vec![]));
+    }
+
+    #[test]
+    fn long_cycle() {
+        let mut graph = Graph::new();
+        let size = 200_000;
+        for id in 0..size {
+            graph.add_node(id);
+        }
+        for id in 0..size {
+            graph.add_edge(id, (id + 1) % size, 1).unwrap();
+        }
+        assert_eq!(graph.strongly_connected_components().count(), 1);
+        graph.remove_edge(size - 1, 0).unwrap();
+        assert_eq!(graph.strongly_connected_components().count(), size as usize);
+    }
+}
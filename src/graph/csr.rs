@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Add;
+
+use super::{Bfs, Dfs, DfsOrder, Direction, Graph, GraphAccess, GraphError, ShortestPaths};
+use super::dijkstra::shortest_paths;
+
+/* Immutable compressed sparse row copy of a Graph.
+
+Nodes are numbered 0..node_count() in the order of ids(). The edges leaving
+node i sit at offsets[i]..offsets[i + 1] of targets and weights, in the order
+they were added to the Graph, so walking a node's edges is a walk over two
+contiguous slices. Undirected edges are stored from both ends like in Graph. */
+#[derive(Clone, Debug)]
+pub struct CsrGraph<T, W> {
+    ids: Vec<T>,
+    index: HashMap<T, u32>,
+    offsets: Vec<usize>,
+    targets: Vec<u32>,
+    weights: Vec<W>,
+    direction: Direction
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy> CsrGraph<T, W> {
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// counts the stored edges, undirected edges other than self loops count twice
+    pub fn edge_count(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// the node ids, in index order
+    pub fn ids(&self) -> &[T] {
+        &self.ids
+    }
+
+    /// returns the dense index given to id
+    /// returns None if id isn't in the graph
+    pub fn index_of(&self, id: T) -> Option<u32> {
+        self.index.get(&id).copied()
+    }
+
+    /// returns the id of the node at index
+    /// panics if index is not below node_count()
+    pub fn id_of(&self, index: u32) -> T {
+        self.ids[index as usize]
+    }
+
+    /// checks to see if id is a node in the graph
+    pub fn contains_node(&self, id: T) -> bool {
+        self.index.contains_key(&id)
+    }
+
+    /// the indices of the nodes the node at index has edges to, in edge order
+    pub fn targets_of(&self, index: u32) -> &[u32] {
+        &self.targets[self.edge_range(index)]
+    }
+
+    /// the weights of the edges leaving the node at index, lined up with targets_of
+    pub fn weights_of(&self, index: u32) -> &[W] {
+        &self.weights[self.edge_range(index)]
+    }
+
+    /// Walks the edges leaving id as (target, weight), nothing if id isn't in the graph
+    pub fn out_edges(&self, id: T) -> impl Iterator<Item=(T, W)> + '_ {
+        let range = match self.index_of(id) {
+            Some(i) => self.edge_range(i),
+            None => 0..0
+        };
+        self.targets[range.clone()].iter()
+            .zip(self.weights[range].iter())
+            .map(move |(&to, &weight)| (self.ids[to as usize], weight))
+    }
+
+    /// counts the edges leaving a node, 0 if it isn't in the graph
+    pub fn out_degree(&self, id: T) -> usize {
+        self.index_of(id).map_or(0, |i| self.edge_range(i).len())
+    }
+
+    /// the raw arrays, edges of node i are at offsets[i]..offsets[i + 1] of targets and weights
+    pub fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+
+    pub fn targets(&self) -> &[u32] {
+        &self.targets
+    }
+
+    pub fn weights(&self) -> &[W] {
+        &self.weights
+    }
+
+    /// Walks the graph breadth first from start, same order as Graph::bfs
+    /// Returns an error if start is not a node in the graph
+    pub fn bfs(&self, start: T) -> Result<Bfs<'_, Self>, GraphError<T>> {
+        Bfs::new(self, start)
+    }
+
+    /// Walks the graph depth first from start, same order as Graph::dfs_with_order
+    /// Returns an error if start is not a node in the graph
+    pub fn dfs_with_order(&self, start: T, order: DfsOrder) -> Result<Dfs<'_, Self>, GraphError<T>> {
+        Dfs::new(self, start, order)
+    }
+
+    /// Walks the graph depth first from start, yielding nodes in pre-order
+    /// Returns an error if start is not a node in the graph
+    pub fn dfs(&self, start: T) -> Result<Dfs<'_, Self>, GraphError<T>> {
+        self.dfs_with_order(start, DfsOrder::PreOrder)
+    }
+
+    fn edge_range(&self, index: u32) -> std::ops::Range<usize> {
+        self.offsets[index as usize]..self.offsets[index as usize + 1]
+    }
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy+Add<Output=W>+Default> CsrGraph<T, W> {
+    /// Runs Dijkstra's algorithm out from source, same as Graph::shortest_paths_from
+    /// Returns an error if source is not a node in the graph.
+    pub fn shortest_paths_from(&self, source: T) -> Result<ShortestPaths<T, W>, GraphError<T>> {
+        shortest_paths(self, source)
+    }
+}
+
+/// Iterator over the edges leaving a node of a CsrGraph as (target index, weight)
+pub struct CsrEdges<'a, W> {
+    targets: std::slice::Iter<'a, u32>,
+    weights: std::slice::Iter<'a, W>
+}
+
+impl<'a, W: Copy> Iterator for CsrEdges<'a, W> {
+    type Item = (usize, W);
+    fn next(&mut self) -> Option<Self::Item> {
+        Some((*self.targets.next()? as usize, *self.weights.next()?))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.targets.size_hint()
+    }
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy> GraphAccess for CsrGraph<T, W> {
+    type Node = T;
+    type Weight = W;
+    type OutEdges<'a> = CsrEdges<'a, W> where Self: 'a;
+
+    fn node_bound(&self) -> usize {
+        self.ids.len()
+    }
+
+    fn node_index(&self, id: T) -> Option<usize> {
+        self.index.get(&id).map(|&i| i as usize)
+    }
+
+    fn node_at(&self, index: usize) -> Option<T> {
+        self.ids.get(index).copied()
+    }
+
+    fn out_edges_at(&self, index: usize) -> CsrEdges<'_, W> {
+        let range = if index < self.ids.len() { self.edge_range(index as u32) } else { 0..0 };
+        CsrEdges {
+            targets: self.targets[range.clone()].iter(),
+            weights: self.weights[range].iter()
+        }
+    }
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy, N, E> Graph<T, W, N, E> {
+    /// Copies the structure of the graph into an immutable CsrGraph, dropping the payloads
+    /// Panics if there are more nodes than fit in a u32
+    pub fn freeze(&self) -> CsrGraph<T, W> {
+        assert!(self.nodes.len() <= u32::MAX as usize, "CsrGraph indexes nodes with u32");
+        let (ids, index) = self.index_nodes();
+        let index: HashMap<T, u32> = index.into_iter().map(|(id, i)| (id, i as u32)).collect();
+        let mut offsets = Vec::with_capacity(ids.len() + 1);
+        let mut targets = Vec::new();
+        let mut weights = Vec::new();
+        offsets.push(0);
+        for &id in &ids {
+            for edge in self.out_edges(id) {
+                targets.push(index[&edge.node_to]);
+                weights.push(edge.weight);
+            }
+            offsets.push(targets.len());
+        }
+        CsrGraph {
+            ids,
+            index,
+            offsets,
+            targets,
+            weights,
+            direction: self.direction
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Duplicates;
+
+    fn init() -> Graph<char, u32, &'static str, ()> {
+        let mut graph = Graph::with_payloads(Direction::Directed, Duplicates::Allow);
+        for id in "abcdef".chars() {
+            graph.add_node_with(id, "payload");
+        }
+        graph.add_edge('a', 'b', 7).unwrap();
+        graph.add_edge('a', 'c', 9).unwrap();
+        graph.add_edge('a', 'f', 14).unwrap();
+        graph.add_edge('b', 'c', 10).unwrap();
+        graph.add_edge('b', 'd', 15).unwrap();
+        graph.add_edge('c', 'd', 11).unwrap();
+        graph.add_edge('c', 'f', 2).unwrap();
+        graph.add_edge('f', 'e', 9).unwrap();
+        graph.add_edge('d', 'e', 6).unwrap();
+        graph
+    }
+
+    #[test]
+    fn layout() {
+        let graph = init();
+        let csr = graph.freeze();
+        assert_eq!(csr.node_count(), 6);
+        assert_eq!(csr.edge_count(), 9);
+        assert_eq!(csr.offsets().len(), 7);
+        assert_eq!(csr.offsets()[6], 9);
+        let a = csr.index_of('a').unwrap();
+        assert_eq!(csr.id_of(a), 'a');
+        let targets: Vec<char> = csr.targets_of(a).iter().map(|&i| csr.id_of(i)).collect();
+        assert_eq!(targets, vec!['b', 'c', 'f']);
+        assert_eq!(csr.weights_of(a), &[7, 9, 14]);
+        assert_eq!(csr.out_edges('c').collect::<Vec<_>>(), vec![('d', 11), ('f', 2)]);
+        assert_eq!(csr.out_edges('z').count(), 0);
+        assert_eq!(csr.out_degree('e'), 0);
+        assert_eq!(csr.index_of('z'), None);
+    }
+
+    #[test]
+    fn walks_match_graph() {
+        let graph = init();
+        let csr = graph.freeze();
+        for id in "abcdef".chars() {
+            assert_eq!(csr.bfs(id).unwrap().collect::<Vec<_>>(), graph.bfs(id).unwrap().collect::<Vec<_>>());
+            assert_eq!(csr.dfs(id).unwrap().collect::<Vec<_>>(), graph.dfs(id).unwrap().collect::<Vec<_>>());
+            let post_order = csr.dfs_with_order(id, DfsOrder::PostOrder).unwrap().collect::<Vec<_>>();
+            assert_eq!(post_order, graph.dfs_post_order(id).unwrap().collect::<Vec<_>>());
+        }
+        assert_eq!(csr.bfs('z').err(), Some(GraphError::NodeNotFound('z')));
+    }
+
+    #[test]
+    fn shortest_paths_match_graph() {
+        let graph = init();
+        let csr = graph.freeze();
+        let frozen = csr.shortest_paths_from('a').unwrap();
+        let live = graph.shortest_paths_from('a').unwrap();
+        assert_eq!(frozen.distances(), live.distances());
+        assert_eq!(frozen.distance_to('e'), Some(20));
+        assert_eq!(frozen.path_to('e'), Some(vec!['a', 'c', 'f', 'e']));
+        assert_eq!(csr.shortest_paths_from('z').err(), Some(GraphError::NodeNotFound('z')));
+    }
+
+    #[test]
+    fn undirected_and_frozen_copy() {
+        let mut graph = Graph::new_undirected();
+        for id in 0..4 {
+            graph.add_node(id);
+        }
+        graph.add_edge(0, 1, 1).unwrap();
+        graph.add_edge(1, 2, 1).unwrap();
+        graph.add_edge(3, 3, 1).unwrap();
+        let csr = graph.freeze();
+        assert_eq!(csr.direction(), Direction::Undirected);
+        assert_eq!(csr.edge_count(), 5);
+        assert_eq!(csr.out_edges(1).collect::<Vec<_>>(), vec![(0, 1), (2, 1)]);
+        graph.remove_node(1).unwrap();
+        assert_eq!(csr.bfs(0).unwrap().count(), 3);
+        assert_eq!(graph.bfs(0).unwrap().count(), 1);
+    }
+
+    // any GraphAccess can be walked through the same code
+    fn reachable<G: GraphAccess>(graph: &G, start: G::Node) -> usize {
+        Bfs::new(graph, start).map_or(0, |walk| walk.count())
+    }
+
+    #[test]
+    fn generic_over_access() {
+        let graph = init();
+        assert_eq!(reachable(&graph, 'b'), 5);
+        assert_eq!(reachable(&graph.freeze(), 'b'), 5);
+        assert_eq!(reachable(&graph, 'z'), 0);
+    }
+}
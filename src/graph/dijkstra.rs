@@ -0,0 +1,434 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Add;
+
+use crate::heap::Heap;
+use super::{Candidate, EdgeRef, Graph, GraphAccess, GraphError, PathSearch};
+
+/// Distances and predecessors found by a single source shortest path search
+#[derive(Clone, Debug)]
+pub struct ShortestPaths<T, W> {
+    source: T,
+    distances: HashMap<T, W>,
+    predecessors: HashMap<T, T>,
+    unreachable: Vec<T>
+}
+
+impl<T: Hash+Eq+Copy, W: Copy> ShortestPaths<T, W> {
+    // anything in nodes without a distance is recorded as unreachable
+    pub(super) fn new<I: Iterator<Item=T>>(source: T, distances: HashMap<T, W>, predecessors: HashMap<T, T>, nodes: I) -> Self {
+        let unreachable = nodes.filter(|id| !distances.contains_key(id)).collect();
+        ShortestPaths {
+            source,
+            distances,
+            predecessors,
+            unreachable
+        }
+    }
+
+    /// the node the search started from
+    pub fn source(&self) -> T {
+        self.source
+    }
+
+    /// returns the length of the shortest path to target
+    /// returns None if target can't be reached from the source
+    pub fn distance_to(&self, target: T) -> Option<W> {
+        self.distances.get(&target).copied()
+    }
+
+    /// checks to see if there is any path from the source to target
+    pub fn is_reachable(&self, target: T) -> bool {
+        self.distances.contains_key(&target)
+    }
+
+    /// returns the node visited right before target on its shortest path
+    /// returns None for the source and for unreachable nodes
+    pub fn predecessor(&self, target: T) -> Option<T> {
+        self.predecessors.get(&target).copied()
+    }
+
+    /// distances to every reachable node, including the source itself
+    pub fn distances(&self) -> &HashMap<T, W> {
+        &self.distances
+    }
+
+    /// the predecessor of every reachable node other than the source
+    pub fn predecessors(&self) -> &HashMap<T, T> {
+        &self.predecessors
+    }
+
+    /// every node in the graph that has no path from the source
+    pub fn unreachable(&self) -> &[T] {
+        &self.unreachable
+    }
+
+    /// Rebuilds the shortest path from the source to target, both ends included
+    /// Returns None if target can't be reached from the source
+    pub fn path_to(&self, target: T) -> Option<Vec<T>> {
+        if !self.is_reachable(target) {
+            return None;
+        }
+        let mut path = vec![target];
+        let mut cur = target;
+        while let Some(&prev) = self.predecessors.get(&cur) {
+            path.push(prev);
+            cur = prev;
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy+Add<Output=W>+Default, N, E> Graph<T, W, N, E> {
+    /// Runs Dijkstra's algorithm out from source using a min ordered Heap.
+    /// W::default() is used as the zero distance, and all edge weights are
+    /// expected to be non-negative.
+    /// Returns an error if source is not a node in the graph.
+    pub fn shortest_paths_from(&self, source: T) -> Result<ShortestPaths<T, W>, GraphError<T>> {
+        shortest_paths(self, source)
+    }
+
+    /// Runs Dijkstra's algorithm with weight(edge) as the length of every edge in place of
+    /// the stored weight, so lengths can come from the edge payloads. Lengths are expected
+    /// to be non-negative.
+    /// Returns an error if source is not a node in the graph.
+    pub fn shortest_paths_by<F: Fn(&EdgeRef<T, W, E>) -> W>(&self, source: T, weight: F) -> Result<ShortestPaths<T, W>, GraphError<T>> {
+        self.check_node(source)?;
+        let (distances, predecessors) = self.dijkstra_with(source, |_, edge| weight(edge));
+        Ok(ShortestPaths::new(source, distances, predecessors, self.nodes.keys().copied()))
+    }
+
+    // Dijkstra's algorithm using weight(from, edge) in place of the stored edge weights,
+    // returns the distance and predecessor maps of every reachable node
+    pub(super) fn dijkstra_with<F: Fn(T, &EdgeRef<T, W, E>) -> W>(&self, source: T, weight: F) -> (HashMap<T, W>, HashMap<T, T>) {
+        dijkstra(self, source, |node, i, _| weight(node, &self.edge_at(node, self.edge_slots(node)[i])))
+    }
+
+    /// Finds a cheapest path from start to goal with Dijkstra's algorithm run from both ends
+    /// at once, stopping once the two searches can't improve on the best meeting point. Edge
+    /// weights must not be negative. The backward search follows incoming edges, so directed
+    /// graphs need the reverse index for it, without the index only the forward search runs.
+    /// Returns an error if start or goal is not a node in the graph.
+    pub fn bidirectional_dijkstra(&self, start: T, goal: T) -> Result<PathSearch<T, W>, GraphError<T>> {
+        self.check_node(start)?;
+        self.check_node(goal)?;
+        // without the index every incoming edge lookup scans all the edges
+        let both_ways = !self.is_directed() || self.has_reverse_index();
+        let mut forward: Frontier<T, W> = Frontier::new(start);
+        let mut backward = Frontier::new(goal);
+        // (cost, node) of the cheapest path found so far through a node both searches reached
+        let mut meeting = if start == goal { Some((W::default(), start)) } else { None };
+        let mut expanded = 0;
+        let mut forward_turn = true;
+
+        loop {
+            let (side, other) = if forward_turn { (&mut forward, &backward) } else { (&mut backward, &forward) };
+            let (node, cost) = match side.settle() {
+                Some(settled) => settled,
+                None => break
+            };
+            // every path not yet found costs at least what both searches have settled so far
+            if let Some((best, _)) = meeting {
+                if side.reach + other.reach >= best {
+                    break;
+                }
+            }
+            expanded += 1;
+            if forward_turn {
+                side.expand(other, node, cost, self.out_edges(node).map(|edge| (edge.node_to, edge.weight)), &mut meeting);
+            } else {
+                side.expand(other, node, cost, self.in_edges(node).map(|edge| (edge.node_from, edge.weight)), &mut meeting);
+            }
+            forward_turn = !forward_turn || !both_ways;
+        }
+
+        let route = meeting.map(|(_, middle)| {
+            let mut path = forward.path_to(middle);
+            let mut rest = backward.path_to(middle);
+            rest.pop();
+            rest.reverse();
+            path.extend(rest);
+            (path, forward.best_cost[&middle] + backward.best_cost[&middle])
+        });
+        Ok(PathSearch::new(route, expanded))
+    }
+}
+
+// one end of a bidirectional search, a Dijkstra search out from root
+struct Frontier<T, W: Ord> {
+    root: T,
+    best_cost: HashMap<T, W>,
+    predecessors: HashMap<T, T>,
+    open: Heap<Reverse<Candidate<W, T>>>,
+    // cost of the node settled last, no node settled later is cheaper
+    reach: W
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy+Add<Output=W>+Default> Frontier<T, W> {
+    fn new(root: T) -> Self {
+        let mut best_cost = HashMap::new();
+        best_cost.insert(root, W::default());
+        let mut open = Heap::new();
+        open.push(Reverse(Candidate::new(W::default(), root)));
+        Frontier {
+            root,
+            best_cost,
+            predecessors: HashMap::new(),
+            open,
+            reach: W::default()
+        }
+    }
+
+    // takes the cheapest node off the open set as (node, cost), skipping replaced entries
+    fn settle(&mut self) -> Option<(T, W)> {
+        while let Some(Reverse(Candidate { priority, node })) = self.open.pop() {
+            if priority <= self.best_cost[&node] {
+                self.reach = priority;
+                return Some((node, priority));
+            }
+        }
+        None
+    }
+
+    // relaxes the (next, weight) steps out of node, which was settled at cost, and records
+    // any cheaper path through a node the other search has reached in meeting
+    fn expand<I: Iterator<Item=(T, W)>>(&mut self, other: &Self, node: T, cost: W, steps: I, meeting: &mut Option<(W, T)>) {
+        for (next, weight) in steps {
+            let next_cost = cost + weight;
+            let improved = match self.best_cost.get(&next) {
+                Some(&known) => next_cost < known,
+                None => true
+            };
+            if !improved {
+                continue;
+            }
+            self.best_cost.insert(next, next_cost);
+            self.predecessors.insert(next, node);
+            self.open.push(Reverse(Candidate::new(next_cost, next)));
+            if let Some(&other_cost) = other.best_cost.get(&next) {
+                let total = next_cost + other_cost;
+                let cheaper = match *meeting {
+                    Some((best, _)) => total < best,
+                    None => true
+                };
+                if cheaper {
+                    *meeting = Some((total, next));
+                }
+            }
+        }
+    }
+
+    // the nodes from root to node, both included
+    fn path_to(&self, node: T) -> Vec<T> {
+        let mut path = vec![node];
+        let mut cur = node;
+        while cur != self.root {
+            cur = self.predecessors[&cur];
+            path.push(cur);
+        }
+        path.reverse();
+        path
+    }
+}
+
+// Dijkstra's algorithm with the stored edge weights, for anything implementing GraphAccess
+pub(super) fn shortest_paths<T, W, G>(graph: &G, source: T) -> Result<ShortestPaths<T, W>, GraphError<T>>
+    where T: Hash+Eq+Copy, W: Ord+Copy+Add<Output=W>+Default, G: GraphAccess<Node=T, Weight=W> {
+    graph.require_node(source)?;
+    let (distances, predecessors) = dijkstra(graph, source, |_, _, weight| weight);
+    let ids = (0..graph.node_bound()).filter_map(|index| graph.node_at(index));
+    Ok(ShortestPaths::new(source, distances, predecessors, ids))
+}
+
+// Dijkstra's algorithm using length(from, i, weight) as the length of the i-th edge out of from,
+// returns the distance and predecessor maps of every reachable node
+fn dijkstra<T, W, G, F>(graph: &G, source: T, length: F) -> (HashMap<T, W>, HashMap<T, T>)
+    where T: Hash+Eq+Copy, W: Ord+Copy+Add<Output=W>+Default, G: GraphAccess<Node=T, Weight=W>, F: Fn(T, usize, W) -> W {
+    // bookkeeping by node index, turned into maps by id at the end
+    let bound = graph.node_bound();
+    let mut distances: Vec<Option<W>> = vec![None; bound];
+    let mut predecessors: Vec<Option<usize>> = vec![None; bound];
+    let mut settled = vec![false; bound];
+    let mut frontier = Heap::new();
+    let id_at = |index| graph.node_at(index).expect("Searches only reach nodes in the graph");
+
+    let start = graph.node_index(source).expect("The source is checked before searching");
+    distances[start] = Some(W::default());
+    frontier.push(Reverse(Candidate::new(W::default(), start)));
+    while let Some(Reverse(Candidate { priority: dist, node })) = frontier.pop() {
+        // stale entries are left in the heap instead of being decreased
+        if settled[node] {
+            continue;
+        }
+        settled[node] = true;
+        let id = id_at(node);
+        for (i, (next, weight)) in graph.out_edges_at(node).enumerate() {
+            let next_dist = dist + length(id, i, weight);
+            let improved = match distances[next] {
+                Some(known) => next_dist < known,
+                None => true
+            };
+            if improved {
+                distances[next] = Some(next_dist);
+                predecessors[next] = Some(node);
+                frontier.push(Reverse(Candidate::new(next_dist, next)));
+            }
+        }
+    }
+    let distances = distances.into_iter().enumerate()
+        .filter_map(|(index, dist)| dist.map(|dist| (id_at(index), dist)))
+        .collect();
+    let predecessors = predecessors.into_iter().enumerate()
+        .filter_map(|(index, prev)| prev.map(|prev| (id_at(index), id_at(prev))))
+        .collect();
+    (distances, predecessors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+    use super::super::Direction;
+
+    fn init() -> Graph<char, u32> {
+        let mut graph = Graph::new();
+        for id in "abcdef".chars() {
+            graph.add_node(id);
+        }
+        graph.add_edge('a', 'b', 7).unwrap();
+        graph.add_edge('a', 'c', 9).unwrap();
+        graph.add_edge('a', 'f', 14).unwrap();
+        graph.add_edge('b', 'c', 10).unwrap();
+        graph.add_edge('b', 'd', 15).unwrap();
+        graph.add_edge('c', 'd', 11).unwrap();
+        graph.add_edge('c', 'f', 2).unwrap();
+        graph.add_edge('f', 'e', 9).unwrap();
+        graph.add_edge('d', 'e', 6).unwrap();
+        graph
+    }
+
+    #[test]
+    fn missing_source() {
+        let graph = init();
+        assert_eq!(graph.shortest_paths_from('z').err(), Some(GraphError::NodeNotFound('z')));
+    }
+
+    #[test]
+    fn distances_from_source() {
+        let graph = init();
+        let paths = graph.shortest_paths_from('a').unwrap();
+        assert_eq!(paths.source(), 'a');
+        assert_eq!(paths.distance_to('a'), Some(0));
+        assert_eq!(paths.distance_to('b'), Some(7));
+        assert_eq!(paths.distance_to('c'), Some(9));
+        assert_eq!(paths.distance_to('d'), Some(20));
+        assert_eq!(paths.distance_to('e'), Some(20));
+        assert_eq!(paths.distance_to('f'), Some(11));
+        assert!(paths.unreachable().is_empty());
+    }
+
+    #[test]
+    fn path_reconstruction() {
+        let graph = init();
+        let paths = graph.shortest_paths_from('a').unwrap();
+        assert_eq!(paths.path_to('a'), Some(vec!['a']));
+        assert_eq!(paths.path_to('e'), Some(vec!['a', 'c', 'f', 'e']));
+        assert_eq!(paths.path_to('d'), Some(vec!['a', 'c', 'd']));
+        assert_eq!(paths.predecessor('f'), Some('c'));
+        assert_eq!(paths.predecessor('a'), None);
+    }
+
+    #[test]
+    fn unreachable_nodes() {
+        let mut graph = init();
+        graph.add_node('g');
+        let paths = graph.shortest_paths_from('c').unwrap();
+        assert!(!paths.is_reachable('a'));
+        assert!(!paths.is_reachable('g'));
+        assert_eq!(paths.distance_to('a'), None);
+        assert_eq!(paths.path_to('b'), None);
+        let mut unreachable = paths.unreachable().to_vec();
+        unreachable.sort();
+        assert_eq!(unreachable, vec!['a', 'b', 'g']);
+    }
+
+    #[test]
+    fn parallel_edges_use_cheapest() {
+        let mut graph = Graph::new();
+        graph.add_node(1);
+        graph.add_node(2);
+        graph.add_edge(1, 2, 10).unwrap();
+        graph.add_edge(1, 2, 3).unwrap();
+        let paths = graph.shortest_paths_from(1).unwrap();
+        assert_eq!(paths.distance_to(2), Some(3));
+    }
+
+    // directed graph on 0..n with random weights, parallel edges and self loops included
+    fn random_weighted(n: i32, edges: usize, seed: u64) -> Graph<i32, i32> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut graph = Graph::new();
+        for id in 0..n {
+            graph.add_node(id);
+        }
+        for _ in 0..edges {
+            graph.add_edge(rng.gen_range(0..n), rng.gen_range(0..n), rng.gen_range(0..20)).unwrap();
+        }
+        graph
+    }
+
+    #[test]
+    fn bidirectional_matches_dijkstra() {
+        for seed in 0..5 {
+            let mut graph = random_weighted(80, 240, seed);
+            for &indexed in [false, true].iter() {
+                if indexed {
+                    graph.enable_reverse_index();
+                }
+                let paths = graph.shortest_paths_from(0).unwrap();
+                for goal in 0..80 {
+                    let search = graph.bidirectional_dijkstra(0, goal).unwrap();
+                    assert_eq!(search.cost(), paths.distance_to(goal));
+                    if let Some(path) = search.path() {
+                        assert_eq!((path[0], path[path.len() - 1]), (0, goal));
+                        let total: i32 = path.windows(2).map(|step| {
+                            graph.out_edges(step[0]).filter(|edge| edge.to() == step[1]).map(|edge| edge.weight()).min().unwrap()
+                        }).sum();
+                        assert_eq!(Some(total), search.cost());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bidirectional_expands_less() {
+        let graph = Graph::<usize, u32>::grid(30, 30, Direction::Undirected, 1);
+        let search = graph.bidirectional_dijkstra(0, 899).unwrap();
+        assert_eq!(search.cost(), Some(58));
+        assert_eq!(search.path().unwrap().len(), 59);
+        let one_way = graph.astar(0, 899, |_| 0).unwrap();
+        assert!(search.expanded() < one_way.expanded());
+
+        let same = graph.bidirectional_dijkstra(45, 45).unwrap();
+        assert_eq!(same.path(), Some(&[45][..]));
+        assert_eq!(same.cost(), Some(0));
+    }
+
+    #[test]
+    fn bidirectional_unreachable_goal() {
+        let mut graph = init();
+        graph.add_node('g');
+        let search = graph.bidirectional_dijkstra('a', 'g').unwrap();
+        assert!(!search.is_found());
+        assert_eq!(search.cost(), None);
+        assert_eq!(graph.bidirectional_dijkstra('a', 'z').err(), Some(GraphError::NodeNotFound('z')));
+
+        let mut one_way = Graph::<usize, u32>::path(5, Direction::Directed, 1);
+        one_way.enable_reverse_index();
+        assert_eq!(one_way.bidirectional_dijkstra(0, 4).unwrap().cost(), Some(4));
+        assert!(!one_way.bidirectional_dijkstra(4, 0).unwrap().is_found());
+    }
+}
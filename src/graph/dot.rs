@@ -0,0 +1,547 @@
+use std::error::Error;
+use std::fmt::{self, Display, Write};
+use std::hash::Hash;
+use std::str::FromStr;
+
+use super::{Direction, Duplicates, Graph};
+
+/// What to draw attention to when writing a graph out as DOT
+#[derive(Clone, Copy, Debug)]
+pub enum Highlight<'a, T> {
+    /// draw everything the same
+    Nothing,
+    /// highlight these nodes
+    Nodes(&'a [T]),
+    /// highlight the nodes of a path along with the edges between consecutive nodes
+    Path(&'a [T])
+}
+
+/// Errors returned when reading a graph from DOT, line numbers start at 1
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DotError {
+    /// the input doesn't follow the supported subset of DOT
+    Syntax { line: usize, expected: &'static str },
+    /// the input uses a part of DOT that isn't supported, like subgraphs or ports
+    Unsupported { line: usize, feature: &'static str },
+    /// a node id couldn't be parsed into the node type
+    BadId { line: usize, id: String },
+    /// an edge weight or label couldn't be parsed into the weight type
+    BadWeight { line: usize, weight: String },
+    /// an edge written with -> in a graph, or with -- in a digraph
+    WrongEdgeOp { line: usize }
+}
+
+impl Display for DotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DotError::Syntax { line, expected } => write!(f, "line {}: expected {}", line, expected),
+            DotError::Unsupported { line, feature } => write!(f, "line {}: {} are not supported", line, feature),
+            DotError::BadId { line, id } => write!(f, "line {}: {:?} is not a valid node id", line, id),
+            DotError::BadWeight { line, weight } => write!(f, "line {}: {:?} is not a valid edge weight", line, weight),
+            DotError::WrongEdgeOp { line } => write!(f, "line {}: edge operator doesn't match the graph's direction", line)
+        }
+    }
+}
+
+impl Error for DotError {}
+
+impl<T: Hash+Eq+Copy+Display, W: Ord+Copy+Display, N, E> Graph<T, W, N, E> {
+    /// Writes the graph as a Graphviz DOT document with edge weights as labels.
+    /// Undirected edges are written once. Payloads are left out. Nodes are written in node
+    /// index order, which reuses the indices of removed nodes, and edges in slot order, so
+    /// the same sequence of changes always gives the same document.
+    pub fn to_dot(&self) -> String {
+        self.to_dot_highlighting(Highlight::Nothing)
+    }
+
+    /// Writes the graph as a Graphviz DOT document, drawing the highlighted nodes
+    /// and path edges in red
+    pub fn to_dot_highlighting(&self, highlight: Highlight<T>) -> String {
+        let (keyword, op) = match self.direction {
+            Direction::Directed => ("digraph", "->"),
+            Direction::Undirected => ("graph", "--")
+        };
+        let (nodes, path) = match highlight {
+            Highlight::Nothing => (&[][..], &[][..]),
+            Highlight::Nodes(nodes) => (nodes, &[][..]),
+            Highlight::Path(path) => (path, path)
+        };
+        let on_path = |from: T, to: T| path.windows(2).any(|step| {
+            (step[0] == from && step[1] == to) || (!self.is_directed() && step[0] == to && step[1] == from)
+        });
+
+        // writing to a String can't fail
+        let mut dot = String::new();
+        writeln!(dot, "{} {{", keyword).unwrap();
+        for &id in self.node_slots.iter().flatten() {
+            write!(dot, "    {}", quote(id)).unwrap();
+            if nodes.contains(&id) {
+                dot.push_str(" [color=red, penwidth=2]");
+            }
+            dot.push_str(";\n");
+        }
+        for edge in self.edges() {
+            write!(dot, "    {} {} {} [label={}", quote(edge.node_from), op, quote(edge.node_to), quote(edge.weight)).unwrap();
+            if on_path(edge.node_from, edge.node_to) {
+                dot.push_str(", color=red, penwidth=2");
+            }
+            dot.push_str("];\n");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl<T: Hash+Eq+Copy+FromStr, W: Ord+Copy+FromStr+Default> Graph<T, W> {
+    /// Reads a graph from a subset of DOT: one graph or digraph holding node statements,
+    /// edge statements (chains like a -> b -> c included) and attribute statements.
+    /// Edge weights come from the weight attribute, or the label attribute if there is no
+    /// weight, and default to W::default(). Other attributes are ignored. A strict graph
+    /// keeps one edge per pair of nodes, a repeated edge replaces the weight of the first.
+    /// Returns an error naming the line if the input can't be read.
+    pub fn from_dot(input: &str) -> Result<Self, DotError> {
+        let tokens = tokenize(input)?;
+        Parser { tokens, pos: 0 }.graph()
+    }
+}
+
+fn quote<D: Display>(value: D) -> String {
+    let text = value.to_string();
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    // bare or quoted id, the flag is set for quoted ids which are never keywords
+    Id(String, bool),
+    Open,
+    Close,
+    OpenAttrs,
+    CloseAttrs,
+    Equals,
+    Separator,
+    EdgeOp(Direction),
+    Colon
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, DotError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    let mut line = 1;
+    let mut after_newline = true;
+    while let Some(c) = chars.next() {
+        let line_start = after_newline;
+        after_newline = c == '\n';
+        let token = match c {
+            '\n' => {
+                line += 1;
+                continue;
+            },
+            c if c.is_whitespace() => continue,
+            '#' if line_start => {
+                // preprocessor style line, skipped like a comment
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+                continue;
+            },
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+                continue;
+            },
+            '/' if chars.peek() == Some(&'*') => {
+                let start = line;
+                chars.next();
+                let mut last = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if last == '*' => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            last = c;
+                        },
+                        None => return Err(DotError::Syntax { line: start, expected: "end of comment" })
+                    }
+                }
+                continue;
+            },
+            '{' => Token::Open,
+            '}' => Token::Close,
+            '[' => Token::OpenAttrs,
+            ']' => Token::CloseAttrs,
+            '=' => Token::Equals,
+            ';' | ',' => Token::Separator,
+            ':' => Token::Colon,
+            '-' if chars.peek() == Some(&'>') => {
+                chars.next();
+                Token::EdgeOp(Direction::Directed)
+            },
+            '-' if chars.peek() == Some(&'-') => {
+                chars.next();
+                Token::EdgeOp(Direction::Undirected)
+            },
+            '"' => {
+                let start = line;
+                let mut id = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('"') => id.push('"'),
+                            Some('\\') => id.push('\\'),
+                            // an escaped newline continues the string
+                            Some('\n') => line += 1,
+                            Some(c) => {
+                                id.push('\\');
+                                id.push(c);
+                            },
+                            None => return Err(DotError::Syntax { line: start, expected: "closing quote" })
+                        },
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            id.push(c);
+                        },
+                        None => return Err(DotError::Syntax { line: start, expected: "closing quote" })
+                    }
+                }
+                Token::Id(id, true)
+            },
+            c if is_id_char(c) => {
+                let mut id = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if !is_id_char(next) || (next == '-' && starts_edge_op(chars.clone())) {
+                        break;
+                    }
+                    id.push(next);
+                    chars.next();
+                }
+                Token::Id(id, false)
+            },
+            _ => return Err(DotError::Syntax { line, expected: "an id, bracket or edge operator" })
+        };
+        tokens.push((token, line));
+    }
+    Ok(tokens)
+}
+
+fn is_id_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.' || c == '-'
+}
+
+// checks if the rest of the input starts with -> or --, so a-->b splits into a, --, b
+fn starts_edge_op(mut rest: std::iter::Peekable<std::str::Chars>) -> bool {
+    rest.next() == Some('-') && matches!(rest.peek(), Some('-') | Some('>'))
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize
+}
+
+impl Parser {
+    fn graph<T: Hash+Eq+Copy+FromStr, W: Ord+Copy+FromStr+Default>(&mut self) -> Result<Graph<T, W>, DotError> {
+        let strict = self.keyword("strict");
+        if strict {
+            self.pos += 1;
+        }
+        let direction = if self.keyword("digraph") {
+            Direction::Directed
+        } else if self.keyword("graph") {
+            Direction::Undirected
+        } else {
+            return Err(self.expected("graph or digraph"));
+        };
+        self.pos += 1;
+        if let Some(Token::Id(..)) = self.peek() {
+            self.pos += 1;
+        }
+        self.expect(Token::Open, "{")?;
+
+        // like Graphviz, a strict graph takes the attributes of the last copy of an edge
+        let duplicates = if strict { Duplicates::Merge(|_, new| new) } else { Duplicates::Allow };
+        let mut graph = Graph::with_mode(direction, duplicates);
+        loop {
+            match self.peek() {
+                Some(Token::Close) => break,
+                Some(Token::Separator) => self.pos += 1,
+                Some(Token::Id(..)) => self.statement(&mut graph)?,
+                _ => return Err(self.expected("a statement or }"))
+            }
+        }
+        self.pos += 1;
+        if self.pos < self.tokens.len() {
+            return Err(self.expected("end of input"));
+        }
+        Ok(graph)
+    }
+
+    fn statement<T: Hash+Eq+Copy+FromStr, W: Ord+Copy+FromStr+Default>(&mut self, graph: &mut Graph<T, W>) -> Result<(), DotError> {
+        if self.keyword("subgraph") {
+            return Err(DotError::Unsupported { line: self.line(), feature: "subgraphs" });
+        }
+        if ["graph", "node", "edge"].iter().any(|&word| self.keyword(word)) {
+            self.pos += 1;
+            self.attributes()?;
+            return Ok(());
+        }
+        let (first, line) = self.id()?;
+        if self.peek() == Some(&Token::Equals) {
+            // graph attribute
+            self.pos += 1;
+            self.id()?;
+            return Ok(());
+        }
+
+        let mut chain = vec![(first, line)];
+        while let Some(&Token::EdgeOp(op)) = self.peek() {
+            if op != graph.direction() {
+                return Err(DotError::WrongEdgeOp { line: self.line() });
+            }
+            self.pos += 1;
+            chain.push(self.id()?);
+        }
+        if self.peek() == Some(&Token::Colon) {
+            return Err(DotError::Unsupported { line: self.line(), feature: "ports" });
+        }
+        let attributes = self.attributes()?;
+
+        let mut ids = Vec::with_capacity(chain.len());
+        for (id, line) in chain {
+            let parsed = id.parse().map_err(|_| DotError::BadId { line, id })?;
+            graph.add_node(parsed);
+            ids.push(parsed);
+        }
+        if ids.len() > 1 {
+            let weight = attributes.iter().find(|(key, _)| key == "weight")
+                .or_else(|| attributes.iter().find(|(key, _)| key == "label"));
+            let weight = match weight {
+                Some((_, text)) => text.parse().map_err(|_| DotError::BadWeight { line, weight: text.clone() })?,
+                None => W::default()
+            };
+            for step in ids.windows(2) {
+                if graph.add_edge(step[0], step[1], weight).is_err() {
+                    unreachable!("Both ends were just added as nodes");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // any number of [key=value, ...] lists
+    fn attributes(&mut self) -> Result<Vec<(String, String)>, DotError> {
+        let mut attributes = Vec::new();
+        while self.peek() == Some(&Token::OpenAttrs) {
+            self.pos += 1;
+            loop {
+                match self.peek() {
+                    Some(Token::CloseAttrs) => break,
+                    Some(Token::Separator) => self.pos += 1,
+                    _ => {
+                        let (key, _) = self.id()?;
+                        self.expect(Token::Equals, "=")?;
+                        let (value, _) = self.id()?;
+                        attributes.push((key, value));
+                    }
+                }
+            }
+            self.pos += 1;
+        }
+        Ok(attributes)
+    }
+
+    fn id(&mut self) -> Result<(String, usize), DotError> {
+        match self.tokens.get(self.pos) {
+            Some((Token::Id(id, _), line)) => {
+                self.pos += 1;
+                Ok((id.clone(), *line))
+            },
+            _ => Err(self.expected("an id"))
+        }
+    }
+
+    fn expect(&mut self, token: Token, expected: &'static str) -> Result<(), DotError> {
+        if self.peek() == Some(&token) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.expected(expected))
+        }
+    }
+
+    // DOT keywords are case insensitive, and never quoted
+    fn keyword(&self, word: &str) -> bool {
+        match self.peek() {
+            Some(Token::Id(id, false)) => id.eq_ignore_ascii_case(word),
+            _ => false
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        match self.tokens.get(self.pos).or_else(|| self.tokens.last()) {
+            Some(&(_, line)) => line,
+            None => 1
+        }
+    }
+
+    fn expected(&self, expected: &'static str) -> DotError {
+        DotError::Syntax { line: self.line(), expected }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted_edges(graph: &Graph<i32, i32>) -> Vec<(i32, i32, i32)> {
+        let mut edges: Vec<(i32, i32, i32)> = graph.nodes()
+            .flat_map(|id| graph.out_edges(id).map(move |edge| (id, edge.to(), edge.weight())))
+            .collect();
+        edges.sort();
+        edges
+    }
+
+    fn sorted_nodes(graph: &Graph<i32, i32>) -> Vec<i32> {
+        let mut nodes: Vec<i32> = graph.nodes().collect();
+        nodes.sort();
+        nodes
+    }
+
+    fn init(direction: Direction) -> Graph<i32, i32> {
+        let mut graph = Graph::with_mode(direction, Duplicates::Allow);
+        for id in 1..=5 {
+            graph.add_node(id);
+        }
+        graph.add_edge(1, 2, 4).unwrap();
+        graph.add_edge(2, 3, -1).unwrap();
+        graph.add_edge(3, 1, 7).unwrap();
+        graph.add_edge(3, 3, 2).unwrap();
+        graph.add_edge(1, 2, 6).unwrap();
+        graph
+    }
+
+    #[test]
+    fn round_trip() {
+        for &direction in [Direction::Directed, Direction::Undirected].iter() {
+            let graph = init(direction);
+            let copy: Graph<i32, i32> = Graph::from_dot(&graph.to_dot()).unwrap();
+            assert_eq!(copy.direction(), direction);
+            assert_eq!(sorted_nodes(&copy), sorted_nodes(&graph));
+            assert_eq!(sorted_edges(&copy), sorted_edges(&graph));
+        }
+    }
+
+    #[test]
+    fn writes_dot() {
+        let mut graph = Graph::new_undirected();
+        graph.add_node('a');
+        graph.add_node('b');
+        graph.add_edge('a', 'b', 3).unwrap();
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("graph {\n"));
+        assert!(dot.contains("    \"a\";\n"));
+        assert!(dot.contains("    \"a\" -- \"b\" [label=\"3\"];\n"));
+        assert!(dot.ends_with("}\n"));
+        assert_eq!(dot.matches("--").count(), 1);
+    }
+
+    #[test]
+    fn highlighting() {
+        let graph = init(Direction::Directed);
+        let dot = graph.to_dot_highlighting(Highlight::Nodes(&[5]));
+        assert!(dot.contains("\"5\" [color=red, penwidth=2];"));
+        assert_eq!(dot.matches("color=red").count(), 1);
+
+        let dot = graph.to_dot_highlighting(Highlight::Path(&[3, 1, 2]));
+        assert!(dot.contains("\"3\" -> \"1\" [label=\"7\", color=red, penwidth=2];"));
+        assert!(dot.contains("\"1\" -> \"2\" [label=\"6\", color=red, penwidth=2];"));
+        assert!(dot.contains("\"2\" -> \"3\" [label=\"-1\"];"));
+        // three nodes and both parallel edges from 1 to 2 along with the edge from 3 to 1
+        assert_eq!(dot.matches("color=red").count(), 6);
+    }
+
+    #[test]
+    fn reads_dot_subset() {
+        let input = r#"
+            strict digraph "routes" {
+                // defaults are ignored
+                rankdir = LR;
+                node [shape=circle]
+                1 -> 2 -> 3 [weight=5, color=blue];
+                /* a node with
+                   attributes */
+                4 [label="four"]
+                "3" -> 1 [label="-2"]
+                5->1
+            }
+        "#;
+        let graph: Graph<i32, i32> = Graph::from_dot(input).unwrap();
+        assert!(graph.is_directed());
+        assert_eq!(sorted_nodes(&graph), vec![1, 2, 3, 4, 5]);
+        assert_eq!(sorted_edges(&graph), vec![(1, 2, 5), (2, 3, 5), (3, 1, -2), (5, 1, 0)]);
+    }
+
+    #[test]
+    fn strict_graphs_merge_edges() {
+        let input = "strict graph {\n 1 -- 2 [weight=3]\n 2 -- 1 [weight=4]\n 1 -- 3\n}";
+        let graph: Graph<i32, i32> = Graph::from_dot(input).unwrap();
+        assert!(!graph.is_multigraph());
+        assert_eq!(sorted_edges(&graph), vec![(1, 2, 4), (1, 3, 0), (2, 1, 4), (3, 1, 0)]);
+        let loose: Graph<i32, i32> = Graph::from_dot(&input["strict ".len()..]).unwrap();
+        assert_eq!(loose.edge_count(), 3);
+    }
+
+    #[test]
+    fn hash_comments_only_at_line_start() {
+        let graph: Graph<i32, i32> = Graph::from_dot("# 1 \"generated\"\ndigraph {\n#line 2\n 1 -> 2\n}").unwrap();
+        assert_eq!(sorted_edges(&graph), vec![(1, 2, 0)]);
+        let err = Graph::<i32, i32>::from_dot("digraph {\n 1 -> 2 # not a comment\n}").err().unwrap();
+        assert_eq!(err, DotError::Syntax { line: 2, expected: "an id, bracket or edge operator" });
+    }
+
+    #[test]
+    fn output_is_deterministic() {
+        let mut graph: Graph<i32, i32> = Graph::new();
+        for id in (0..50).rev() {
+            graph.add_node(id);
+        }
+        graph.add_edge(7, 3, 1).unwrap();
+        graph.add_edge(3, 7, 2).unwrap();
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph {\n    \"49\";\n    \"48\";\n"));
+        assert!(dot.ends_with("    \"7\" -> \"3\" [label=\"1\"];\n    \"3\" -> \"7\" [label=\"2\"];\n}\n"));
+        let copy: Graph<i32, i32> = Graph::from_dot(&dot).unwrap();
+        assert_eq!(copy.to_dot(), dot);
+    }
+
+    #[test]
+    fn read_errors() {
+        let parse = |input: &str| Graph::<i32, i32>::from_dot(input).err().unwrap();
+        assert_eq!(parse("graph {\n 1 -> 2\n}"), DotError::WrongEdgeOp { line: 2 });
+        assert_eq!(parse("digraph {\n\n a -> 2\n}"), DotError::BadId { line: 3, id: "a".to_string() });
+        assert_eq!(parse("digraph {\n 1 -> 2 [label=x]\n}"), DotError::BadWeight { line: 2, weight: "x".to_string() });
+        assert_eq!(parse("digraph {\n subgraph { 1 }\n}"), DotError::Unsupported { line: 2, feature: "subgraphs" });
+        assert_eq!(parse("digraph {\n 1:n -> 2\n}"), DotError::Unsupported { line: 2, feature: "ports" });
+        assert_eq!(parse("tree { }"), DotError::Syntax { line: 1, expected: "graph or digraph" });
+        assert_eq!(parse("digraph {\n 1 -> \n}"), DotError::Syntax { line: 3, expected: "an id" });
+        assert_eq!(parse("digraph { \"1 }").to_string(), "line 1: expected closing quote");
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+
+use super::Graph;
+
+/// Reasons a graph has no Eulerian path or circuit
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EulerError<T> {
+    /// the edges don't all belong to one connected piece, the two nodes have edges but
+    /// no walk joins them
+    Disconnected(T, T),
+    /// undirected nodes with an odd number of edge ends, a circuit needs none of them
+    /// and a path needs zero or two
+    OddDegree(Vec<T>),
+    /// directed nodes as (node, out degree, in degree) where the two don't match. A circuit
+    /// needs none of them, and a path allows one start with an extra edge out and one end
+    /// with an extra edge in.
+    Unbalanced(Vec<(T, usize, usize)>)
+}
+
+impl<T: fmt::Debug> fmt::Display for EulerError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EulerError::Disconnected(a, b) => write!(f, "edges at {:?} and {:?} aren't connected, so no single walk can use them all", a, b),
+            EulerError::OddDegree(nodes) => write!(f, "{} nodes have an odd degree ({:?}), a circuit needs none and a path needs zero or two", nodes.len(), nodes),
+            EulerError::Unbalanced(nodes) => {
+                write!(f, "nodes need as many edges in as out, except for the ends of a path, but")?;
+                for (i, (node, out_degree, in_degree)) in nodes.iter().enumerate() {
+                    let separator = if i == 0 { "" } else { "," };
+                    write!(f, "{} {:?} has {} out and {} in", separator, node, out_degree, in_degree)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug> Error for EulerError<T> {}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy, N, E> Graph<T, W, N, E> {
+    /// Finds a closed walk using every edge exactly once with Hierholzer's algorithm. The walk
+    /// starts and ends at the same node, which is listed at both ends. A graph without
+    /// edges gives an empty walk.
+    /// Returns an error explaining which degree condition fails if there is no such walk.
+    pub fn eulerian_circuit(&self) -> Result<Vec<T>, EulerError<T>> {
+        let (odd, unbalanced) = self.degree_imbalance();
+        if !unbalanced.is_empty() {
+            return Err(EulerError::Unbalanced(unbalanced));
+        }
+        if !odd.is_empty() {
+            return Err(EulerError::OddDegree(odd));
+        }
+        let start = self.edges.iter().flatten().next().map(|edge| edge.node_from);
+        self.hierholzer(start)
+    }
+
+    /// Finds a walk using every edge exactly once with Hierholzer's algorithm, a circuit
+    /// if there is one. A graph without edges gives an empty walk.
+    /// Returns an error explaining which degree condition fails if there is no such walk.
+    pub fn eulerian_path(&self) -> Result<Vec<T>, EulerError<T>> {
+        let (odd, unbalanced) = self.degree_imbalance();
+        let start = if self.is_directed() {
+            // the start has one more edge out than in, and the end one more in than out
+            let starts: Vec<T> = unbalanced.iter().filter(|&&(_, out_degree, in_degree)| out_degree == in_degree + 1).map(|&(id, _, _)| id).collect();
+            let ends = unbalanced.iter().filter(|&&(_, out_degree, in_degree)| in_degree == out_degree + 1).count();
+            match (unbalanced.len(), starts.len(), ends) {
+                (0, _, _) => self.edges.iter().flatten().next().map(|edge| edge.node_from),
+                (2, 1, 1) => Some(starts[0]),
+                _ => return Err(EulerError::Unbalanced(unbalanced))
+            }
+        } else {
+            match odd.len() {
+                0 => self.edges.iter().flatten().next().map(|edge| edge.node_from),
+                2 => Some(odd[0]),
+                _ => return Err(EulerError::OddDegree(odd))
+            }
+        };
+        self.hierholzer(start)
+    }
+
+    // nodes with an odd number of edge ends, and nodes as (id, out, in) with out != in,
+    // only the first is filled in for undirected graphs and only the second for directed ones
+    fn degree_imbalance(&self) -> (Vec<T>, Vec<(T, usize, usize)>) {
+        let mut degrees: HashMap<T, (usize, usize)> = HashMap::new();
+        for edge in self.edges.iter().flatten() {
+            degrees.entry(edge.node_from).or_insert((0, 0)).0 += 1;
+            degrees.entry(edge.node_to).or_insert((0, 0)).1 += 1;
+        }
+        if self.is_directed() {
+            let unbalanced = degrees.into_iter()
+                .filter(|&(_, (out_degree, in_degree))| out_degree != in_degree)
+                .map(|(id, (out_degree, in_degree))| (id, out_degree, in_degree))
+                .collect();
+            (Vec::new(), unbalanced)
+        } else {
+            let odd = degrees.into_iter()
+                .filter(|&(_, (out_degree, in_degree))| (out_degree + in_degree) % 2 == 1)
+                .map(|(id, _)| id)
+                .collect();
+            (odd, Vec::new())
+        }
+    }
+
+    // iterative Hierholzer from start once the degree conditions hold, only connectivity can fail
+    fn hierholzer(&self, start: Option<T>) -> Result<Vec<T>, EulerError<T>> {
+        let start = match start {
+            Some(start) => start,
+            None => return Ok(Vec::new())
+        };
+        let mut used = vec![false; self.edges.len()];
+        // index of the next edge to try out of each node
+        let mut next_edge: HashMap<T, usize> = HashMap::new();
+        let mut stack = vec![start];
+        let mut walk = Vec::with_capacity(self.edge_count() + 1);
+        while let Some(&node) = stack.last() {
+            let slots = self.edge_slots(node);
+            let i = next_edge.entry(node).or_insert(0);
+            while *i < slots.len() && used[slots[*i]] {
+                *i += 1;
+            }
+            if *i < slots.len() {
+                let slot = slots[*i];
+                used[slot] = true;
+                stack.push(self.edge_at(node, slot).node_to);
+            } else {
+                walk.push(node);
+                stack.pop();
+            }
+        }
+        walk.reverse();
+
+        if walk.len() != self.edge_count() + 1 {
+            let missed = self.edges.iter().enumerate()
+                .find(|&(slot, edge)| edge.is_some() && !used[slot])
+                .and_then(|(_, edge)| edge.as_ref())
+                .expect("A short walk leaves an edge unused");
+            return Err(EulerError::Disconnected(start, missed.node_from));
+        }
+        Ok(walk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Direction;
+
+    // checks walk uses every edge of the graph exactly once
+    fn assert_uses_every_edge(graph: &Graph<i32, i32>, walk: &[i32]) {
+        let mut remaining: Vec<(i32, i32)> = graph.edges().map(|edge| (edge.from(), edge.to())).collect();
+        assert_eq!(walk.len(), remaining.len() + 1);
+        for step in walk.windows(2) {
+            let pos = remaining.iter().position(|&(a, b)| {
+                (a, b) == (step[0], step[1]) || (!graph.is_directed() && (b, a) == (step[0], step[1]))
+            });
+            remaining.swap_remove(pos.expect("walk follows an edge that is missing or already used"));
+        }
+    }
+
+    fn build(direction: Direction, edges: &[(i32, i32)]) -> Graph<i32, i32> {
+        let mut graph = Graph::with_mode(direction, super::super::Duplicates::Allow);
+        for &(from, to) in edges {
+            graph.add_node(from);
+            graph.add_node(to);
+            graph.add_edge(from, to, 1).unwrap();
+        }
+        graph
+    }
+
+    #[test]
+    fn undirected_circuit() {
+        // two triangles sharing node 3, plus a self loop and a doubled edge
+        let graph = build(Direction::Undirected, &[(1, 2), (2, 3), (3, 1), (3, 4), (4, 5), (5, 3), (4, 4), (1, 2), (2, 1)]);
+        let walk = graph.eulerian_circuit().unwrap();
+        assert_eq!(walk.first(), walk.last());
+        assert_uses_every_edge(&graph, &walk);
+        assert_uses_every_edge(&graph, &graph.eulerian_path().unwrap());
+    }
+
+    #[test]
+    fn undirected_path() {
+        // the house shape, only 1 and 2 have odd degree
+        let graph = build(Direction::Undirected, &[(1, 2), (2, 3), (3, 4), (4, 1), (1, 3), (2, 4), (3, 5), (5, 4)]);
+        match graph.eulerian_circuit() {
+            Err(EulerError::OddDegree(mut odd)) => {
+                odd.sort();
+                assert_eq!(odd, vec![1, 2]);
+            },
+            other => panic!("expected odd degrees, got {:?}", other)
+        }
+        let walk = graph.eulerian_path().unwrap();
+        assert!(walk[0] == 1 || walk[0] == 2);
+        assert_uses_every_edge(&graph, &walk);
+    }
+
+    #[test]
+    fn directed_path_and_circuit() {
+        let graph = build(Direction::Directed, &[(1, 2), (2, 3), (3, 1), (1, 4), (4, 1)]);
+        let walk = graph.eulerian_circuit().unwrap();
+        assert_uses_every_edge(&graph, &walk);
+
+        let graph = build(Direction::Directed, &[(0, 1), (1, 2), (2, 3), (3, 1), (1, 4)]);
+        assert_eq!(graph.eulerian_circuit().err().map(|err| match err {
+            EulerError::Unbalanced(nodes) => nodes.len(),
+            _ => 0
+        }), Some(2));
+        let walk = graph.eulerian_path().unwrap();
+        assert_eq!(walk[0], 0);
+        assert_eq!(*walk.last().unwrap(), 4);
+        assert_uses_every_edge(&graph, &walk);
+    }
+
+    #[test]
+    fn degree_errors() {
+        let star = build(Direction::Undirected, &[(0, 1), (0, 2), (0, 3)]);
+        let err = star.eulerian_path().unwrap_err();
+        assert!(matches!(&err, EulerError::OddDegree(nodes) if nodes.len() == 4));
+        assert!(err.to_string().starts_with("4 nodes have an odd degree"));
+
+        let fork = build(Direction::Directed, &[(0, 1), (0, 2)]);
+        let err = fork.eulerian_path().unwrap_err();
+        match &err {
+            EulerError::Unbalanced(nodes) => assert!(nodes.contains(&(0, 2, 0))),
+            other => panic!("expected unbalanced nodes, got {:?}", other)
+        }
+        assert!(err.to_string().contains("0 has 2 out and 0 in"));
+    }
+
+    #[test]
+    fn disconnected_edges() {
+        let mut graph = build(Direction::Undirected, &[(1, 2), (2, 3), (3, 1), (4, 5), (5, 6), (6, 4)]);
+        graph.add_node(9);
+        assert!(matches!(graph.eulerian_circuit(), Err(EulerError::Disconnected(..))));
+        let graph = build(Direction::Directed, &[(1, 2), (2, 1), (3, 4), (4, 3)]);
+        assert!(matches!(graph.eulerian_path(), Err(EulerError::Disconnected(..))));
+    }
+
+    #[test]
+    fn no_edges() {
+        let mut graph: Graph<i32, i32> = Graph::new();
+        graph.add_node(1);
+        assert_eq!(graph.eulerian_circuit(), Ok(vec![]));
+        assert_eq!(graph.eulerian_path(), Ok(vec![]));
+    }
+
+    #[test]
+    fn long_cycle() {
+        let graph = Graph::<usize, u32>::cycle(100_000, Direction::Directed, 1);
+        assert_eq!(graph.eulerian_circuit().unwrap().len(), 100_001);
+    }
+}
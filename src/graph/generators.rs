@@ -0,0 +1,329 @@
+use std::collections::HashSet;
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use super::{Direction, Duplicates, Graph};
+
+/* Generators for test and benchmark graphs.
+
+Every generator numbers its nodes 0..n and gives every edge the same weight.
+The random ones draw from the rng passed in, so seeding it, for example with
+rand::rngs::StdRng::seed_from_u64, gives the same graph every time. */
+impl<W: Ord+Copy> Graph<usize, W> {
+    /// Erdős–Rényi G(n, p), every possible edge between two different nodes is added with
+    /// probability p. Directed graphs roll for each direction separately.
+    /// Panics if p is not between 0 and 1
+    pub fn gnp<R: Rng+?Sized>(n: usize, p: f64, direction: Direction, weight: W, rng: &mut R) -> Self {
+        assert!((0.0..=1.0).contains(&p), "G(n, p) needs a probability between 0 and 1, got p = {}", p);
+        let mut graph = Self::empty(n, direction);
+        for from in 0..n {
+            let first = if direction == Direction::Directed { 0 } else { from + 1 };
+            for to in first..n {
+                if from != to && rng.gen_bool(p) {
+                    graph.link(from, to, weight);
+                }
+            }
+        }
+        graph
+    }
+
+    /// Erdős–Rényi G(n, m), m different edges between different nodes picked uniformly at random
+    /// Panics if m is more than the number of possible edges
+    pub fn gnm<R: Rng+?Sized>(n: usize, m: usize, direction: Direction, weight: W, rng: &mut R) -> Self {
+        let possible = match direction {
+            Direction::Directed => n * n.saturating_sub(1),
+            Direction::Undirected => n * n.saturating_sub(1) / 2
+        };
+        assert!(m <= possible, "G(n, m) can't have {} edges, there are only {} possible", m, possible);
+        let mut graph = Self::empty(n, direction);
+        // rejection sampling needs the picked set to be the smaller one, so a dense graph
+        // is built by picking the edges to leave out instead
+        let leave_out = m > possible / 2;
+        let picks = if leave_out { possible - m } else { m };
+        let mut picked = HashSet::with_capacity(picks);
+        while picked.len() < picks {
+            let (from, to) = (rng.gen_range(0..n), rng.gen_range(0..n));
+            if from != to {
+                picked.insert(Self::ordered(from, to, direction));
+            }
+        }
+        if leave_out {
+            for from in 0..n {
+                let first = if direction == Direction::Directed { 0 } else { from + 1 };
+                for to in first..n {
+                    if from != to && !picked.contains(&(from, to)) {
+                        graph.link(from, to, weight);
+                    }
+                }
+            }
+        } else {
+            let mut edges: Vec<(usize, usize)> = picked.into_iter().collect();
+            // HashSet order isn't seeded, so sort to keep the edge order reproducible
+            edges.sort_unstable();
+            for (from, to) in edges {
+                graph.link(from, to, weight);
+            }
+        }
+        graph
+    }
+
+    /// Barabási–Albert preferential attachment. Starts from a star on nodes 0..=m and then
+    /// joins every new node to m different existing nodes, picked with probability
+    /// proportional to their degree. The result is undirected.
+    /// Panics unless 1 <= m < n
+    pub fn barabasi_albert<R: Rng+?Sized>(n: usize, m: usize, weight: W, rng: &mut R) -> Self {
+        assert!(m >= 1 && m < n, "Barabási–Albert needs 1 <= m < n, got m = {} and n = {}", m, n);
+        let mut graph = Self::star(m + 1, Direction::Undirected, weight);
+        // every node appears once per edge end, so a uniform pick is proportional to degree
+        let mut ends: Vec<usize> = Vec::with_capacity(2 * m * n);
+        for leaf in 1..=m {
+            ends.extend([0, leaf]);
+        }
+        for node in m + 1..n {
+            graph.add_node(node);
+            let mut targets = HashSet::with_capacity(m);
+            while targets.len() < m {
+                targets.insert(*ends.choose(rng).expect("The starting star has edges"));
+            }
+            let mut targets: Vec<usize> = targets.into_iter().collect();
+            targets.sort_unstable();
+            for target in targets {
+                graph.link(node, target, weight);
+                ends.extend([node, target]);
+            }
+        }
+        graph
+    }
+
+    /// rows x columns lattice where every node is joined to the nodes right of and below it,
+    /// the node at (row, column) is row * columns + column
+    pub fn grid(rows: usize, columns: usize, direction: Direction, weight: W) -> Self {
+        let mut graph = Self::empty(rows * columns, direction);
+        for row in 0..rows {
+            for column in 0..columns {
+                let id = row * columns + column;
+                if column + 1 < columns {
+                    graph.link(id, id + 1, weight);
+                }
+                if row + 1 < rows {
+                    graph.link(id, id + columns, weight);
+                }
+            }
+        }
+        graph
+    }
+
+    /// Every node joined to every other node, directed graphs get an edge each way
+    pub fn complete(n: usize, direction: Direction, weight: W) -> Self {
+        let mut graph = Self::empty(n, direction);
+        for from in 0..n {
+            for to in from + 1..n {
+                graph.link(from, to, weight);
+                if direction == Direction::Directed {
+                    graph.link(to, from, weight);
+                }
+            }
+        }
+        graph
+    }
+
+    /// Node 0 joined to each of the other n - 1 nodes, with edges pointing out from 0
+    pub fn star(n: usize, direction: Direction, weight: W) -> Self {
+        let mut graph = Self::empty(n, direction);
+        for leaf in 1..n {
+            graph.link(0, leaf, weight);
+        }
+        graph
+    }
+
+    /// Nodes 0..n joined in a line, 0 -> 1 -> ... -> n - 1
+    pub fn path(n: usize, direction: Direction, weight: W) -> Self {
+        let mut graph = Self::empty(n, direction);
+        for id in 1..n {
+            graph.link(id - 1, id, weight);
+        }
+        graph
+    }
+
+    /// Nodes 0..n joined in a ring, the path graph with an edge from n - 1 back to 0.
+    /// Below 3 nodes the ring degenerates: one node gets a self loop, and two nodes get a
+    /// second edge between them, which an undirected graph stores as a parallel edge.
+    pub fn cycle(n: usize, direction: Direction, weight: W) -> Self {
+        let mut graph = Self::path(n, direction, weight);
+        if n > 0 {
+            graph.link(n - 1, 0, weight);
+        }
+        graph
+    }
+
+    /// Random directed acyclic graph. The nodes are shuffled into a hidden order and each
+    /// edge from an earlier node to a later one is added with probability p.
+    /// Panics if p is not between 0 and 1
+    pub fn random_dag<R: Rng+?Sized>(n: usize, p: f64, weight: W, rng: &mut R) -> Self {
+        assert!((0.0..=1.0).contains(&p), "a random DAG needs a probability between 0 and 1, got p = {}", p);
+        let mut order: Vec<usize> = (0..n).collect();
+        order.shuffle(rng);
+        let mut graph = Self::empty(n, Direction::Directed);
+        for i in 0..n {
+            for j in i + 1..n {
+                if rng.gen_bool(p) {
+                    graph.link(order[i], order[j], weight);
+                }
+            }
+        }
+        graph
+    }
+
+    fn empty(n: usize, direction: Direction) -> Self {
+        let mut graph = Graph::with_mode(direction, Duplicates::Allow);
+        for id in 0..n {
+            graph.add_node(id);
+        }
+        graph
+    }
+
+    fn link(&mut self, from: usize, to: usize, weight: W) {
+        if self.add_edge(from, to, weight).is_err() {
+            unreachable!("Generators add every node before any edge");
+        }
+    }
+
+    // undirected edges are picked as (smaller, larger) so each one only has one form
+    fn ordered(from: usize, to: usize, direction: Direction) -> (usize, usize) {
+        match direction {
+            Direction::Directed => (from, to),
+            Direction::Undirected => (from.min(to), from.max(to))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn edge_list(graph: &Graph<usize, u32>) -> Vec<(usize, usize)> {
+        let mut edges: Vec<(usize, usize)> = graph.edges().map(|edge| (edge.from(), edge.to())).collect();
+        edges.sort_unstable();
+        edges
+    }
+
+    fn degree_sum(graph: &Graph<usize, u32>) -> usize {
+        graph.nodes().map(|id| graph.out_degree(id)).sum()
+    }
+
+    #[test]
+    fn seeded_generators_are_reproducible() {
+        let build = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            vec![
+                edge_list(&Graph::gnp(30, 0.2, Direction::Directed, 1, &mut rng)),
+                edge_list(&Graph::gnm(30, 50, Direction::Undirected, 1, &mut rng)),
+                edge_list(&Graph::barabasi_albert(30, 2, 1, &mut rng)),
+                edge_list(&Graph::random_dag(30, 0.3, 1, &mut rng))
+            ]
+        };
+        assert_eq!(build(7), build(7));
+        assert_ne!(build(7), build(8));
+    }
+
+    #[test]
+    fn gnp_extremes() {
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(Graph::<usize, u32>::gnp(10, 0.0, Direction::Directed, 1, &mut rng).edge_count(), 0);
+        assert_eq!(Graph::<usize, u32>::gnp(10, 1.0, Direction::Directed, 1, &mut rng).edge_count(), 90);
+        assert_eq!(Graph::<usize, u32>::gnp(10, 1.0, Direction::Undirected, 1, &mut rng).edge_count(), 45);
+    }
+
+    #[test]
+    fn gnm_has_exactly_m_simple_edges() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for &(direction, m) in [(Direction::Directed, 20), (Direction::Directed, 80), (Direction::Undirected, 40)].iter() {
+            let graph = Graph::gnm(10, m, direction, 1u32, &mut rng);
+            let edges = edge_list(&graph);
+            assert_eq!(edges.len(), m);
+            let distinct: HashSet<(usize, usize)> = edges.iter().copied().collect();
+            assert_eq!(distinct.len(), m);
+            assert!(edges.iter().all(|&(from, to)| from != to));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn gnm_rejects_too_many_edges() {
+        let mut rng = StdRng::seed_from_u64(3);
+        Graph::<usize, u32>::gnm(4, 7, Direction::Undirected, 1, &mut rng);
+    }
+
+    #[test]
+    fn barabasi_albert_shape() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let graph = Graph::<usize, u32>::barabasi_albert(200, 3, 1, &mut rng);
+        assert_eq!(graph.node_count(), 200);
+        assert_eq!(graph.edge_count(), 3 + 3 * (200 - 4));
+        assert!(graph.nodes().all(|id| graph.out_degree(id) >= 1));
+        assert!((4..200).all(|id| graph.out_degree(id) >= 3));
+        // early nodes pick up far more edges than the average of 6
+        let biggest = graph.nodes().map(|id| graph.out_degree(id)).max().unwrap();
+        assert!(biggest > 15);
+    }
+
+    #[test]
+    fn structured_graphs() {
+        let grid = Graph::<usize, u32>::grid(3, 4, Direction::Undirected, 1);
+        assert_eq!(grid.node_count(), 12);
+        assert_eq!(grid.edge_count(), 3 * 3 + 2 * 4);
+        assert_eq!(grid.out_degree(5), 4);
+        assert_eq!(grid.out_degree(0), 2);
+
+        let complete = Graph::<usize, u32>::complete(6, Direction::Directed, 1);
+        assert_eq!(complete.edge_count(), 30);
+        let complete = Graph::<usize, u32>::complete(6, Direction::Undirected, 1);
+        assert_eq!(degree_sum(&complete), 30);
+
+        let star = Graph::<usize, u32>::star(5, Direction::Undirected, 1);
+        assert_eq!(star.out_degree(0), 4);
+        assert_eq!(edge_list(&star), vec![(0, 1), (0, 2), (0, 3), (0, 4)]);
+
+        let path = Graph::<usize, u32>::path(4, Direction::Directed, 2);
+        assert_eq!(path.topological_order(), Ok(vec![0, 1, 2, 3]));
+        assert_eq!(path.shortest_paths_from(0).unwrap().distance_to(3), Some(6));
+
+        let cycle = Graph::<usize, u32>::cycle(5, Direction::Directed, 1);
+        assert!(!cycle.is_acyclic());
+        assert_eq!(cycle.strongly_connected_components().count(), 1);
+        assert_eq!(Graph::<usize, u32>::cycle(0, Direction::Directed, 1).node_count(), 0);
+        let single = Graph::<usize, u32>::cycle(1, Direction::Undirected, 1);
+        assert_eq!(edge_list(&single), vec![(0, 0)]);
+        let pair = Graph::<usize, u32>::cycle(2, Direction::Undirected, 1);
+        assert_eq!(pair.edge_count(), 2);
+        assert_eq!(pair.out_degree(0), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "G(n, p) needs a probability between 0 and 1, got p = 1.5")]
+    fn gnp_rejects_bad_probability() {
+        let mut rng = StdRng::seed_from_u64(1);
+        Graph::<usize, u32>::gnp(5, 1.5, Direction::Directed, 1, &mut rng);
+    }
+
+    #[test]
+    #[should_panic(expected = "a random DAG needs a probability between 0 and 1")]
+    fn random_dag_rejects_bad_probability() {
+        let mut rng = StdRng::seed_from_u64(1);
+        Graph::<usize, u32>::random_dag(5, f64::NAN, 1, &mut rng);
+    }
+
+    #[test]
+    fn random_dag_is_acyclic() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for _ in 0..10 {
+            let graph = Graph::<usize, u32>::random_dag(40, 0.2, 1, &mut rng);
+            assert!(graph.is_acyclic());
+        }
+        let dense = Graph::<usize, u32>::random_dag(10, 1.0, 1, &mut rng);
+        assert_eq!(dense.edge_count(), 45);
+    }
+}
@@ -0,0 +1,199 @@
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+
+use super::Graph;
+
+/// Returned when a Hamiltonian search is asked to look at more nodes than its limit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TooManyNodes {
+    nodes: usize,
+    limit: usize
+}
+
+impl TooManyNodes {
+    /// the number of nodes in the graph
+    pub fn nodes(&self) -> usize {
+        self.nodes
+    }
+
+    /// the largest graph the search was allowed to take on
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl fmt::Display for TooManyNodes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "graph has {} nodes, more than the limit of {} for a Hamiltonian search", self.nodes, self.limit)
+    }
+}
+
+impl Error for TooManyNodes {}
+
+/* Hamiltonian searches try every simple path by backtracking, which takes time
+exponential in the number of nodes. They're meant for small graphs such as puzzle
+boards, so the caller passes the largest node count they're prepared to wait for
+and bigger graphs are refused up front rather than left running. */
+impl<T: Hash+Eq+Copy, W: Ord+Copy, N, E> Graph<T, W, N, E> {
+    /// Finds a path following the edges that visits every node exactly once, or None if there
+    /// is no such path. An empty graph gives an empty path.
+    /// Returns an error if the graph has more than max_nodes nodes
+    pub fn hamiltonian_path(&self, max_nodes: usize) -> Result<Option<Vec<T>>, TooManyNodes> {
+        self.hamiltonian(max_nodes, false)
+    }
+
+    /// Finds a cycle following the edges that visits every node exactly once, or None if there
+    /// is no such cycle. Each node is listed once and the last node has an edge back to the
+    /// first. A single node needs a self loop, and two undirected nodes need two parallel edges.
+    /// Returns an error if the graph has more than max_nodes nodes
+    pub fn hamiltonian_cycle(&self, max_nodes: usize) -> Result<Option<Vec<T>>, TooManyNodes> {
+        self.hamiltonian(max_nodes, true)
+    }
+
+    fn hamiltonian(&self, max_nodes: usize, closed: bool) -> Result<Option<Vec<T>>, TooManyNodes> {
+        let n = self.nodes.len();
+        if n > max_nodes {
+            return Err(TooManyNodes { nodes: n, limit: max_nodes });
+        }
+        if n == 0 {
+            return Ok(Some(Vec::new()));
+        }
+        let (ids, index) = self.index_nodes();
+        let adjacency = self.dense_adjacency(&index);
+        // the walk steps without self loops or repeats, fewest onward options first so
+        // dead ends are found early
+        let neighbors: Vec<Vec<usize>> = adjacency.iter().enumerate().map(|(node, targets)| {
+            let mut targets: Vec<usize> = targets.iter().copied().filter(|&target| target != node).collect();
+            targets.sort_unstable_by_key(|&target| (adjacency[target].len(), target));
+            targets.dedup();
+            targets
+        }).collect();
+        // an undirected cycle through two nodes can't go out and back along the same edge
+        let closing_edges = if n == 2 && !self.is_directed() { 2 } else { 1 };
+        let closes = |last: usize, first: usize| {
+            adjacency[last].iter().filter(|&&target| target == first).count() >= closing_edges
+        };
+
+        // every node is on a cycle, so starting from one of them is enough
+        let starts = if closed { 1 } else { n };
+        let mut visited = vec![false; n];
+        for start in 0..starts {
+            let mut path = vec![start];
+            // index of the next neighbor to try from each node on the path
+            let mut next = vec![0];
+            visited[start] = true;
+            while let Some(&node) = path.last() {
+                if path.len() == n && (!closed || closes(node, start)) {
+                    return Ok(Some(path.into_iter().map(|i| ids[i]).collect()));
+                }
+                let tried = next.last_mut().expect("next has an entry per path node");
+                let step = neighbors[node][*tried..].iter().position(|&target| !visited[target]);
+                match step {
+                    Some(offset) => {
+                        let target = neighbors[node][*tried + offset];
+                        *tried += offset + 1;
+                        visited[target] = true;
+                        path.push(target);
+                        next.push(0);
+                    },
+                    None => {
+                        visited[node] = false;
+                        path.pop();
+                        next.pop();
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::Direction;
+
+    // outer 5-cycle 0..5, inner pentagram 5..10 and spokes between them
+    fn petersen() -> Graph<usize, u32> {
+        let mut graph = Graph::cycle(5, Direction::Undirected, 1);
+        for i in 0..5 {
+            graph.add_node(i + 5);
+        }
+        for i in 0..5 {
+            graph.add_edge(i, i + 5, 1).unwrap();
+            graph.add_edge(i + 5, (i + 2) % 5 + 5, 1).unwrap();
+        }
+        graph
+    }
+
+    // checks route visits every node once along edges, and returns to the start if closed
+    fn assert_visits_all(graph: &Graph<usize, u32>, route: &[usize], closed: bool) {
+        let mut sorted = route.to_vec();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..graph.node_count()).collect::<Vec<usize>>());
+        for step in route.windows(2) {
+            assert!(graph.contains_edge(step[0], step[1]));
+        }
+        if closed {
+            assert!(graph.contains_edge(route[route.len() - 1], route[0]));
+        }
+    }
+
+    #[test]
+    fn petersen_has_a_path_but_no_cycle() {
+        let graph = petersen();
+        let path = graph.hamiltonian_path(10).unwrap().unwrap();
+        assert_visits_all(&graph, &path, false);
+        assert_eq!(graph.hamiltonian_cycle(10), Ok(None));
+    }
+
+    #[test]
+    fn grids() {
+        let grid = Graph::<usize, u32>::grid(4, 4, Direction::Undirected, 1);
+        let cycle = grid.hamiltonian_cycle(16).unwrap().unwrap();
+        assert_visits_all(&grid, &cycle, true);
+        // odd by odd grids are bipartite with one side bigger, so only paths exist
+        let grid = Graph::<usize, u32>::grid(3, 5, Direction::Undirected, 1);
+        assert_visits_all(&grid, &grid.hamiltonian_path(16).unwrap().unwrap(), false);
+        assert_eq!(grid.hamiltonian_cycle(16), Ok(None));
+    }
+
+    #[test]
+    fn directed() {
+        let path = Graph::<usize, u32>::path(6, Direction::Directed, 1);
+        assert_eq!(path.hamiltonian_path(6), Ok(Some(vec![0, 1, 2, 3, 4, 5])));
+        assert_eq!(path.hamiltonian_cycle(6), Ok(None));
+        let star = Graph::<usize, u32>::star(3, Direction::Directed, 1);
+        assert_eq!(star.hamiltonian_path(3), Ok(None));
+        let cycle = Graph::<usize, u32>::cycle(7, Direction::Directed, 1);
+        assert_visits_all(&cycle, &cycle.hamiltonian_cycle(7).unwrap().unwrap(), true);
+    }
+
+    #[test]
+    fn tiny_graphs() {
+        let mut graph: Graph<usize, u32> = Graph::new_undirected();
+        assert_eq!(graph.hamiltonian_path(0), Ok(Some(vec![])));
+        graph.add_node(0);
+        assert_eq!(graph.hamiltonian_path(1), Ok(Some(vec![0])));
+        assert_eq!(graph.hamiltonian_cycle(1), Ok(None));
+        graph.add_edge(0, 0, 1).unwrap();
+        assert_eq!(graph.hamiltonian_cycle(1), Ok(Some(vec![0])));
+
+        let mut pair = Graph::<usize, u32>::with_mode(Direction::Undirected, super::super::Duplicates::Allow);
+        pair.add_node(0);
+        pair.add_node(1);
+        pair.add_edge(0, 1, 1).unwrap();
+        assert_eq!(pair.hamiltonian_cycle(2), Ok(None));
+        pair.add_edge(1, 0, 1).unwrap();
+        assert_visits_all(&pair, &pair.hamiltonian_cycle(2).unwrap().unwrap(), true);
+    }
+
+    #[test]
+    fn node_limit() {
+        let graph = petersen();
+        let err = graph.hamiltonian_path(9).unwrap_err();
+        assert_eq!((err.nodes(), err.limit()), (10, 9));
+        assert_eq!(err.to_string(), "graph has 10 nodes, more than the limit of 9 for a Hamiltonian search");
+    }
+}
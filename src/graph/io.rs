@@ -0,0 +1,490 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::hash::Hash;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+use super::{Direction, Duplicates, Graph};
+
+/// Errors returned when reading a graph from text, line numbers start at 1
+#[derive(Debug)]
+pub enum ReadError {
+    /// the reader itself failed
+    Io(io::Error),
+    /// a node id couldn't be parsed into the node type
+    BadId { line: usize, text: String },
+    /// an edge weight couldn't be parsed into the weight type
+    BadWeight { line: usize, text: String },
+    /// a line is missing a field
+    MissingField { line: usize, expected: &'static str },
+    /// a line has more fields than the format allows
+    ExtraField { line: usize, text: String },
+    /// the Matrix Market header or size line is missing or not supported
+    BadHeader { line: usize, expected: &'static str },
+    /// a Matrix Market entry names a row or column outside the declared size
+    IndexOutOfRange { line: usize, index: usize },
+    /// the number of Matrix Market entries doesn't match the size line
+    EntryCount { line: usize, expected: usize, found: usize }
+}
+
+impl Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Io(err) => write!(f, "failed to read graph: {}", err),
+            ReadError::BadId { line, text } => write!(f, "line {}: {:?} is not a valid node id", line, text),
+            ReadError::BadWeight { line, text } => write!(f, "line {}: {:?} is not a valid edge weight", line, text),
+            ReadError::MissingField { line, expected } => write!(f, "line {}: missing {}", line, expected),
+            ReadError::ExtraField { line, text } => write!(f, "line {}: unexpected {:?}", line, text),
+            ReadError::BadHeader { line, expected } => write!(f, "line {}: expected {}", line, expected),
+            ReadError::IndexOutOfRange { line, index } => write!(f, "line {}: index {} is outside the matrix", line, index),
+            ReadError::EntryCount { line, expected, found } => write!(f, "line {}: expected {} entries but found {}", line, expected, found)
+        }
+    }
+}
+
+impl Error for ReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ReadError::Io(err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for ReadError {
+    fn from(err: io::Error) -> Self {
+        ReadError::Io(err)
+    }
+}
+
+impl<T: Hash+Eq+Copy+FromStr, W: Ord+Copy+FromStr+Default> Graph<T, W> {
+    /// Reads a whitespace separated edge list, one `from to [weight]` edge per line.
+    /// A line with a single id adds a node without edges, missing weights are W::default(),
+    /// and blank lines or lines starting with # or % are skipped.
+    /// Returns an error naming the line if the input can't be read.
+    pub fn read_edge_list<R: BufRead>(reader: R, direction: Direction) -> Result<Self, ReadError> {
+        let mut graph = Graph::with_mode(direction, Duplicates::Allow);
+        let mut lines = DataLines::new(reader, &['#', '%']);
+        while let Some((number, line)) = lines.next_line()? {
+            let mut fields = line.split_whitespace();
+            let from = parse_id(fields.next(), number)?;
+            graph.add_node(from);
+            let to = match fields.next() {
+                Some(text) => parse_id(Some(text), number)?,
+                None => continue
+            };
+            let weight = match fields.next() {
+                Some(text) => parse_weight(text, number)?,
+                None => W::default()
+            };
+            if let Some(text) = fields.next() {
+                return Err(ReadError::ExtraField { line: number, text: text.to_string() });
+            }
+            graph.add_node(to);
+            if graph.add_edge(from, to, weight).is_err() {
+                unreachable!("Both ends were just added as nodes");
+            }
+        }
+        Ok(graph)
+    }
+
+    /// Reads an adjacency list, one `node to:weight to:weight ...` line per node.
+    /// Neighbors without a :weight get W::default(), and neighbors don't need a line of their own.
+    /// Every entry adds an edge, so undirected input has to list each edge under only one of its
+    /// ends like write_adjacency_list does, listing it under both gives two parallel edges.
+    /// Blank lines or lines starting with # or % are skipped.
+    /// Returns an error naming the line if the input can't be read.
+    pub fn read_adjacency_list<R: BufRead>(reader: R, direction: Direction) -> Result<Self, ReadError> {
+        let mut graph = Graph::with_mode(direction, Duplicates::Allow);
+        let mut lines = DataLines::new(reader, &['#', '%']);
+        while let Some((number, line)) = lines.next_line()? {
+            let mut fields = line.split_whitespace();
+            let from = parse_id(fields.next(), number)?;
+            graph.add_node(from);
+            for field in fields {
+                let (to, weight) = match field.rfind(':') {
+                    Some(split) => (&field[..split], parse_weight(&field[split + 1..], number)?),
+                    None => (field, W::default())
+                };
+                let to = parse_id(Some(to), number)?;
+                graph.add_node(to);
+                if graph.add_edge(from, to, weight).is_err() {
+                    unreachable!("Both ends were just added as nodes");
+                }
+            }
+        }
+        Ok(graph)
+    }
+}
+
+impl<W: Ord+Copy+FromStr+Default> Graph<usize, W> {
+    /// Reads a Matrix Market coordinate file as an adjacency matrix. Row and column i become
+    /// node i - 1, so the nodes are 0..n for an n by n matrix. Symmetric matrices give an
+    /// undirected graph and general ones a directed graph. Pattern matrices have no values,
+    /// so their edges get W::default().
+    /// Returns an error naming the line if the input can't be read.
+    pub fn read_matrix_market<R: BufRead>(reader: R) -> Result<Self, ReadError> {
+        let mut lines = DataLines::new(reader, &[]);
+        let header = lines.next_line()?.map(|(_, line)| line.to_lowercase()).unwrap_or_default();
+        let header: Vec<&str> = header.split_whitespace().collect();
+        if header.len() != 5 || header[0] != "%%matrixmarket" || header[1] != "matrix" {
+            return Err(ReadError::BadHeader { line: 1, expected: "%%MatrixMarket matrix header" });
+        }
+        if header[2] != "coordinate" {
+            return Err(ReadError::BadHeader { line: 1, expected: "coordinate format" });
+        }
+        let pattern = match header[3] {
+            "pattern" => true,
+            "real" | "integer" => false,
+            _ => return Err(ReadError::BadHeader { line: 1, expected: "real, integer or pattern field" })
+        };
+        let direction = match header[4] {
+            "general" => Direction::Directed,
+            "symmetric" => Direction::Undirected,
+            _ => return Err(ReadError::BadHeader { line: 1, expected: "general or symmetric matrix" })
+        };
+
+        lines.comments = &['%'];
+        let (number, size) = match lines.next_line()? {
+            Some((number, line)) => (number, line.split_whitespace().map(|field| field.parse().ok()).collect()),
+            None => (lines.number, Some(Vec::new()))
+        };
+        let size: Vec<usize> = size.ok_or(ReadError::BadHeader { line: number, expected: "rows, columns and entry count" })?;
+        if size.len() != 3 {
+            return Err(ReadError::BadHeader { line: number, expected: "rows, columns and entry count" });
+        }
+        if size[0] != size[1] {
+            return Err(ReadError::BadHeader { line: number, expected: "a square matrix" });
+        }
+        let (n, expected) = (size[0], size[2]);
+
+        let mut graph = Graph::with_mode(direction, Duplicates::Allow);
+        for id in 0..n {
+            graph.add_node(id);
+        }
+        let mut found = 0;
+        while let Some((number, line)) = lines.next_line()? {
+            found += 1;
+            if found > expected {
+                return Err(ReadError::EntryCount { line: number, expected, found });
+            }
+            let mut fields = line.split_whitespace();
+            let row = parse_index(fields.next(), n, number)?;
+            let column = parse_index(fields.next(), n, number)?;
+            let weight = if pattern {
+                W::default()
+            } else {
+                match fields.next() {
+                    Some(text) => parse_weight(text, number)?,
+                    None => return Err(ReadError::MissingField { line: number, expected: "entry value" })
+                }
+            };
+            if let Some(text) = fields.next() {
+                return Err(ReadError::ExtraField { line: number, text: text.to_string() });
+            }
+            if graph.add_edge(row, column, weight).is_err() {
+                unreachable!("Indices were checked against the size");
+            }
+        }
+        if found < expected {
+            return Err(ReadError::EntryCount { line: lines.number, expected, found });
+        }
+        Ok(graph)
+    }
+}
+
+impl<T: Hash+Eq+Copy+Display, W: Ord+Copy+Display, N, E> Graph<T, W, N, E> {
+    /// Writes the graph as an edge list readable by read_edge_list, nodes without any edges
+    /// get a line of their own and undirected edges are written once
+    pub fn write_edge_list<Wr: Write>(&self, mut writer: Wr) -> io::Result<()> {
+        let touched: HashSet<T> = self.edges().flat_map(|edge| [edge.node_from, edge.node_to]).collect();
+        for &id in self.nodes.keys() {
+            if !touched.contains(&id) {
+                writeln!(writer, "{}", id)?;
+            }
+        }
+        for edge in self.edges() {
+            writeln!(writer, "{} {} {}", edge.node_from, edge.node_to, edge.weight)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the graph as an adjacency list readable by read_adjacency_list, one line per node
+    /// Undirected edges are written once, on the line of the node they were added from
+    pub fn write_adjacency_list<Wr: Write>(&self, mut writer: Wr) -> io::Result<()> {
+        for node in self.nodes.values() {
+            write!(writer, "{}", node.id)?;
+            for &slot in &node.edges {
+                let edge = self.edges[slot].as_ref().expect("Nodes only list slots of live edges");
+                if edge.node_from == node.id {
+                    write!(writer, " {}:{}", edge.node_to, edge.weight)?;
+                }
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Ord+Copy+Display, N, E> Graph<usize, W, N, E> {
+    /// Writes the graph as a Matrix Market coordinate file readable by read_matrix_market,
+    /// node i becoming row and column i + 1 of a matrix big enough for the largest id.
+    /// Ids are not renumbered, so a graph with gaps in its ids is padded with empty rows and
+    /// reading it back gives a node for every id below the largest one.
+    /// Undirected graphs are written as symmetric matrices. The field is integer when every
+    /// weight is written as a whole number, and real otherwise.
+    pub fn write_matrix_market<Wr: Write>(&self, mut writer: Wr) -> io::Result<()> {
+        let symmetry = match self.direction {
+            Direction::Directed => "general",
+            Direction::Undirected => "symmetric"
+        };
+        let field = if self.edges().all(|edge| is_integer(&edge.weight.to_string())) { "integer" } else { "real" };
+        let n = self.nodes.keys().max().map_or(0, |&id| id + 1);
+        writeln!(writer, "%%MatrixMarket matrix coordinate {} {}", field, symmetry)?;
+        writeln!(writer, "{} {} {}", n, n, self.edge_count())?;
+        for edge in self.edges() {
+            let (row, column) = match self.direction {
+                Direction::Directed => (edge.node_from, edge.node_to),
+                // symmetric files only hold the lower triangle
+                Direction::Undirected => (edge.node_from.max(edge.node_to), edge.node_from.min(edge.node_to))
+            };
+            writeln!(writer, "{} {} {}", row + 1, column + 1, edge.weight)?;
+        }
+        Ok(())
+    }
+}
+
+// checks if text is a whole number like -12, as a Matrix Market integer entry has to be
+fn is_integer(text: &str) -> bool {
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+// Non blank lines of a reader that don't start with a comment marker, reusing one buffer
+struct DataLines<R> {
+    reader: R,
+    buffer: String,
+    comments: &'static [char],
+    // number of the line last returned
+    number: usize
+}
+
+impl<R: BufRead> DataLines<R> {
+    fn new(reader: R, comments: &'static [char]) -> Self {
+        DataLines {
+            reader,
+            buffer: String::new(),
+            comments,
+            number: 0
+        }
+    }
+
+    // the next data line as (line number, trimmed text), borrowed from the buffer
+    fn next_line(&mut self) -> Result<Option<(usize, &str)>, ReadError> {
+        loop {
+            self.buffer.clear();
+            if self.reader.read_line(&mut self.buffer)? == 0 {
+                return Ok(None);
+            }
+            self.number += 1;
+            let line = self.buffer.trim();
+            if !line.is_empty() && !line.starts_with(self.comments) {
+                break;
+            }
+        }
+        // borrowed again out here, a borrow returned from inside the loop would outlive the clear
+        Ok(Some((self.number, self.buffer.trim())))
+    }
+}
+
+fn parse_id<T: FromStr>(text: Option<&str>, line: usize) -> Result<T, ReadError> {
+    let text = text.ok_or(ReadError::MissingField { line, expected: "node id" })?;
+    text.parse().map_err(|_| ReadError::BadId { line, text: text.to_string() })
+}
+
+fn parse_weight<W: FromStr>(text: &str, line: usize) -> Result<W, ReadError> {
+    text.parse().map_err(|_| ReadError::BadWeight { line, text: text.to_string() })
+}
+
+// 1 based matrix index to 0 based node id
+fn parse_index(text: Option<&str>, n: usize, line: usize) -> Result<usize, ReadError> {
+    let text = text.ok_or(ReadError::MissingField { line, expected: "row and column" })?;
+    let index: usize = text.parse().map_err(|_| ReadError::BadId { line, text: text.to_string() })?;
+    if index == 0 || index > n {
+        return Err(ReadError::IndexOutOfRange { line, index });
+    }
+    Ok(index - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted_edges(graph: &Graph<usize, i32>) -> Vec<(usize, usize, i32)> {
+        let mut edges: Vec<(usize, usize, i32)> = graph.nodes()
+            .flat_map(|id| graph.out_edges(id).map(move |edge| (id, edge.to(), edge.weight())))
+            .collect();
+        edges.sort();
+        edges
+    }
+
+    fn sorted_nodes(graph: &Graph<usize, i32>) -> Vec<usize> {
+        let mut nodes: Vec<usize> = graph.nodes().collect();
+        nodes.sort();
+        nodes
+    }
+
+    fn init(direction: Direction) -> Graph<usize, i32> {
+        let mut graph = Graph::with_mode(direction, Duplicates::Allow);
+        for id in 0..6 {
+            graph.add_node(id);
+        }
+        graph.add_edge(0, 1, 4).unwrap();
+        graph.add_edge(1, 2, -3).unwrap();
+        graph.add_edge(2, 0, 7).unwrap();
+        graph.add_edge(3, 3, 2).unwrap();
+        graph.add_edge(0, 1, 5).unwrap();
+        graph.add_edge(4, 0, 1).unwrap();
+        graph
+    }
+
+    fn assert_same(a: &Graph<usize, i32>, b: &Graph<usize, i32>) {
+        assert_eq!(a.direction(), b.direction());
+        assert_eq!(sorted_nodes(a), sorted_nodes(b));
+        assert_eq!(sorted_edges(a), sorted_edges(b));
+    }
+
+    #[test]
+    fn edge_list_round_trip() {
+        for &direction in [Direction::Directed, Direction::Undirected].iter() {
+            let graph = init(direction);
+            let mut text = Vec::new();
+            graph.write_edge_list(&mut text).unwrap();
+            assert!(String::from_utf8(text.clone()).unwrap().lines().any(|line| line == "5"));
+            let copy = Graph::read_edge_list(&text[..], direction).unwrap();
+            assert_same(&graph, &copy);
+        }
+    }
+
+    #[test]
+    fn adjacency_list_round_trip() {
+        for &direction in [Direction::Directed, Direction::Undirected].iter() {
+            let graph = init(direction);
+            let mut text = Vec::new();
+            graph.write_adjacency_list(&mut text).unwrap();
+            let copy = Graph::read_adjacency_list(&text[..], direction).unwrap();
+            assert_same(&graph, &copy);
+        }
+    }
+
+    #[test]
+    fn matrix_market_round_trip() {
+        for &direction in [Direction::Directed, Direction::Undirected].iter() {
+            let graph = init(direction);
+            let mut text = Vec::new();
+            graph.write_matrix_market(&mut text).unwrap();
+            let copy = Graph::read_matrix_market(&text[..]).unwrap();
+            assert_same(&graph, &copy);
+        }
+    }
+
+    // weights in tenths, written with a decimal point
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+    struct Tenths(i32);
+
+    impl Display for Tenths {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}.{}", self.0 / 10, self.0 % 10)
+        }
+    }
+
+    #[test]
+    fn matrix_market_field_and_sparse_ids() {
+        let mut graph: Graph<usize, i32> = Graph::new();
+        graph.add_node(1);
+        graph.add_node(4);
+        graph.add_edge(4, 1, -3).unwrap();
+        let mut text = Vec::new();
+        graph.write_matrix_market(&mut text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert_eq!(text, "%%MatrixMarket matrix coordinate integer general\n5 5 1\n5 2 -3\n");
+        // the gaps below the largest id come back as nodes without edges
+        let copy = Graph::<usize, i32>::read_matrix_market(text.as_bytes()).unwrap();
+        assert_eq!(sorted_nodes(&copy), vec![0, 1, 2, 3, 4]);
+        assert_eq!(sorted_edges(&copy), vec![(4, 1, -3)]);
+
+        let mut graph: Graph<usize, Tenths> = Graph::new_undirected();
+        graph.add_node(0);
+        graph.add_edge(0, 0, Tenths(15)).unwrap();
+        let mut text = Vec::new();
+        graph.write_matrix_market(&mut text).unwrap();
+        assert_eq!(String::from_utf8(text).unwrap(), "%%MatrixMarket matrix coordinate real symmetric\n1 1 1\n1 1 1.5\n");
+        assert!(is_integer("-12") && is_integer("0"));
+        assert!(!is_integer("-") && !is_integer("1e5") && !is_integer("2.0"));
+    }
+
+    #[test]
+    fn reads_edge_list() {
+        let text = "# comment\n1 2 5\n\n2 3\n% also a comment\n  4  \n";
+        let graph: Graph<usize, i32> = Graph::read_edge_list(text.as_bytes(), Direction::Directed).unwrap();
+        assert_eq!(sorted_nodes(&graph), vec![1, 2, 3, 4]);
+        assert_eq!(sorted_edges(&graph), vec![(1, 2, 5), (2, 3, 0)]);
+    }
+
+    #[test]
+    fn reads_adjacency_list() {
+        let text = "a b:3 c\nb a:-1\n";
+        let graph: Graph<char, i32> = Graph::read_adjacency_list(text.as_bytes(), Direction::Directed).unwrap();
+        assert_eq!(graph.get_edges_from('a'), Some(vec![('b', 3), ('c', 0)]));
+        assert_eq!(graph.get_edges_from('b'), Some(vec![('a', -1)]));
+        assert_eq!(graph.get_edges_from('c'), Some(vec![]));
+    }
+
+    #[test]
+    fn reads_matrix_market() {
+        let text = "%%MatrixMarket matrix coordinate pattern symmetric\n% comment\n3 3 2\n2 1\n3 3\n";
+        let graph: Graph<usize, i32> = Graph::read_matrix_market(text.as_bytes()).unwrap();
+        assert!(!graph.is_directed());
+        assert_eq!(sorted_nodes(&graph), vec![0, 1, 2]);
+        assert_eq!(sorted_edges(&graph), vec![(0, 1, 0), (1, 0, 0), (2, 2, 0)]);
+    }
+
+    #[test]
+    fn read_errors() {
+        let edges = |text: &str| Graph::<usize, i32>::read_edge_list(text.as_bytes(), Direction::Directed).err().unwrap().to_string();
+        assert_eq!(edges("1 2\n\nx 3\n"), "line 3: \"x\" is not a valid node id");
+        assert_eq!(edges("1 2 w\n"), "line 1: \"w\" is not a valid edge weight");
+        assert_eq!(edges("1 2 3 4\n"), "line 1: unexpected \"4\"");
+
+        let adjacency = |text: &str| Graph::<usize, i32>::read_adjacency_list(text.as_bytes(), Direction::Directed).err().unwrap();
+        match adjacency("1 2:3\n2 3:x\n") {
+            ReadError::BadWeight { line: 2, text } => assert_eq!(text, "x"),
+            other => panic!("unexpected error {:?}", other)
+        }
+
+        let matrix = |text: &str| Graph::<usize, i32>::read_matrix_market(text.as_bytes()).err().unwrap().to_string();
+        assert_eq!(matrix("3 3 1\n1 1 1\n"), "line 1: expected %%MatrixMarket matrix header");
+        assert_eq!(matrix("%%MatrixMarket matrix array real general\n"), "line 1: expected coordinate format");
+        assert_eq!(matrix("%%MatrixMarket matrix coordinate real general\n2 3 0\n"), "line 2: expected a square matrix");
+        assert_eq!(matrix("%%MatrixMarket matrix coordinate real general\n2 2 1\n3 1 1\n"), "line 3: index 3 is outside the matrix");
+        assert_eq!(matrix("%%MatrixMarket matrix coordinate real general\n2 2 1\n1 1\n"), "line 3: missing entry value");
+        assert_eq!(matrix("%%MatrixMarket matrix coordinate real general\n2 2 2\n1 1 1\n"), "line 3: expected 2 entries but found 1");
+        assert_eq!(matrix("%%MatrixMarket matrix coordinate real general\n2 2 0\n1 1 1\n"), "line 3: expected 0 entries but found 1");
+    }
+
+    struct FailingReader;
+
+    impl io::Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk on fire"))
+        }
+    }
+
+    #[test]
+    fn io_errors_pass_through() {
+        let err = Graph::<usize, i32>::read_edge_list(io::BufReader::new(FailingReader), Direction::Directed).err().unwrap();
+        assert!(matches!(err, ReadError::Io(_)));
+        assert!(err.source().is_some());
+    }
+}
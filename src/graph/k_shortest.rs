@@ -0,0 +1,182 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::ops::Add;
+
+use crate::heap::Heap;
+use super::{Candidate, Graph, GraphError};
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy+Add<Output=W>+Default, N, E> Graph<T, W, N, E> {
+    /// Finds up to k loopless paths from start to goal with Yen's algorithm, cheapest first,
+    /// as (path, total weight) with both ends included in each path. Paths are told apart by
+    /// the nodes they visit, so parallel edges only count once with their lowest weight.
+    /// Edge weights must not be negative.
+    /// Returns an error if start or goal is not a node in the graph.
+    pub fn k_shortest_paths(&self, start: T, goal: T, k: usize) -> Result<Vec<(Vec<T>, W)>, GraphError<T>> {
+        self.check_node(start)?;
+        self.check_node(goal)?;
+        let mut found: Vec<(Vec<T>, W)> = Vec::with_capacity(k);
+        if k == 0 {
+            return Ok(found);
+        }
+        match self.spur_search(start, goal, &HashSet::new(), &HashSet::new()) {
+            Some(route) => found.push(route),
+            None => return Ok(found)
+        }
+        // (cost, arrival) ordered so equal costs come out in the order they were found
+        let mut candidates = Heap::new();
+        let mut seen: HashSet<Vec<T>> = HashSet::new();
+        seen.insert(found[0].0.clone());
+
+        while found.len() < k {
+            let previous = found[found.len() - 1].0.clone();
+            let mut root_cost = W::default();
+            for i in 0..previous.len() - 1 {
+                let spur = previous[i];
+                let root = &previous[..=i];
+                // leave the root by an edge no found path with the same root already took
+                let banned_edges: HashSet<(T, T)> = found.iter()
+                    .filter(|(path, _)| path.len() > i + 1 && &path[..=i] == root)
+                    .map(|(path, _)| (path[i], path[i + 1]))
+                    .collect();
+                let banned_nodes: HashSet<T> = previous[..i].iter().copied().collect();
+                if let Some((spur_path, spur_cost)) = self.spur_search(spur, goal, &banned_nodes, &banned_edges) {
+                    let mut path = previous[..i].to_vec();
+                    path.extend(spur_path);
+                    if seen.insert(path.clone()) {
+                        let arrival = seen.len();
+                        candidates.push(Reverse(Candidate::new((root_cost + spur_cost, arrival), path)));
+                    }
+                }
+                root_cost = root_cost + self.cheapest_step(spur, previous[i + 1]);
+            }
+            match candidates.pop() {
+                Some(Reverse(Candidate { priority: (cost, _), node: path })) => found.push((path, cost)),
+                None => break
+            }
+        }
+        Ok(found)
+    }
+
+    // Dijkstra from start to goal that never enters banned_nodes or follows an edge listed in
+    // banned_edges as (from, to), returning the path and its cost
+    fn spur_search(&self, start: T, goal: T, banned_nodes: &HashSet<T>, banned_edges: &HashSet<(T, T)>) -> Option<(Vec<T>, W)> {
+        let mut best_cost = HashMap::new();
+        let mut predecessors = HashMap::new();
+        let mut open = Heap::new();
+        best_cost.insert(start, W::default());
+        open.push(Reverse(Candidate::new(W::default(), start)));
+        while let Some(Reverse(Candidate { priority: cost, node })) = open.pop() {
+            if cost > best_cost[&node] {
+                continue;
+            }
+            if node == goal {
+                let mut path = vec![goal];
+                let mut cur = goal;
+                while let Some(&prev) = predecessors.get(&cur) {
+                    path.push(prev);
+                    cur = prev;
+                }
+                path.reverse();
+                return Some((path, cost));
+            }
+            for edge in self.out_edges(node) {
+                if banned_nodes.contains(&edge.node_to) || banned_edges.contains(&(node, edge.node_to)) {
+                    continue;
+                }
+                let next_cost = cost + edge.weight;
+                let improved = match best_cost.get(&edge.node_to) {
+                    Some(&known) => next_cost < known,
+                    None => true
+                };
+                if improved {
+                    best_cost.insert(edge.node_to, next_cost);
+                    predecessors.insert(edge.node_to, node);
+                    open.push(Reverse(Candidate::new(next_cost, edge.node_to)));
+                }
+            }
+        }
+        None
+    }
+
+    // the lowest weight of the edges from one node of a found path to the next
+    fn cheapest_step(&self, from: T, to: T) -> W {
+        self.out_edges(from).filter(|edge| edge.node_to == to).map(|edge| edge.weight).min()
+            .expect("Found paths only follow existing edges")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{Direction, Duplicates};
+
+    // the example network from Yen's paper as usually drawn, c to h
+    fn network() -> Graph<char, i32> {
+        let mut graph = Graph::new();
+        for id in "cdefgh".chars() {
+            graph.add_node(id);
+        }
+        for &(from, to, weight) in [('c', 'd', 3), ('c', 'e', 2), ('d', 'f', 4), ('e', 'd', 1), ('e', 'f', 2),
+                                     ('e', 'g', 3), ('f', 'g', 2), ('f', 'h', 1), ('g', 'h', 2)].iter() {
+            graph.add_edge(from, to, weight).unwrap();
+        }
+        graph
+    }
+
+    fn costs(paths: &[(Vec<char>, i32)]) -> Vec<i32> {
+        paths.iter().map(|&(_, cost)| cost).collect()
+    }
+
+    #[test]
+    fn paths_come_out_cheapest_first() {
+        let graph = network();
+        let paths = graph.k_shortest_paths('c', 'h', 3).unwrap();
+        assert_eq!(paths[0], ("cefh".chars().collect(), 5));
+        assert_eq!(paths[1], ("cegh".chars().collect(), 7));
+        assert_eq!(paths[2].1, 8);
+        // every simple path from c to h
+        let all = graph.k_shortest_paths('c', 'h', 20).unwrap();
+        assert_eq!(costs(&all), vec![5, 7, 8, 8, 8, 11, 11]);
+        let distinct: HashSet<Vec<char>> = all.iter().map(|(path, _)| path.clone()).collect();
+        assert_eq!(distinct.len(), 7);
+    }
+
+    #[test]
+    fn paths_are_loopless_and_priced_right() {
+        let mut graph: Graph<i32, i32> = Graph::with_mode(Direction::Undirected, Duplicates::Allow);
+        for id in 0..9 {
+            graph.add_node(id);
+        }
+        for id in 0..9 {
+            if id % 3 < 2 {
+                graph.add_edge(id, id + 1, 1 + id % 4).unwrap();
+            }
+            if id < 6 {
+                graph.add_edge(id, id + 3, 2 + id % 3).unwrap();
+            }
+        }
+        // a dearer parallel edge doesn't make a second path
+        graph.add_edge(0, 1, 10).unwrap();
+        let paths = graph.k_shortest_paths(0, 8, 50).unwrap();
+        assert_eq!(paths.len(), 12);
+        for (path, cost) in &paths {
+            let nodes: HashSet<i32> = path.iter().copied().collect();
+            assert_eq!(nodes.len(), path.len());
+            assert_eq!((path[0], path[path.len() - 1]), (0, 8));
+            let total: i32 = path.windows(2).map(|step| graph.cheapest_step(step[0], step[1])).sum();
+            assert_eq!(total, *cost);
+        }
+        assert!(paths.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+        assert_eq!(paths[0].1, graph.bidirectional_dijkstra(0, 8).unwrap().cost().unwrap());
+    }
+
+    #[test]
+    fn edge_cases() {
+        let graph = network();
+        assert_eq!(graph.k_shortest_paths('c', 'h', 0), Ok(vec![]));
+        assert_eq!(graph.k_shortest_paths('h', 'c', 3), Ok(vec![]));
+        assert_eq!(graph.k_shortest_paths('d', 'd', 3), Ok(vec![(vec!['d'], 0)]));
+        assert_eq!(graph.k_shortest_paths('c', 'z', 3), Err(GraphError::NodeNotFound('z')));
+    }
+}
@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::{Add, Sub};
+
+use crate::queue::Queue;
+use super::{Graph, GraphError};
+
+/// Maximum flow between two nodes, along with how it is routed and a matching minimum cut
+#[derive(Clone, Debug)]
+pub struct MaxFlow<T, W> {
+    value: W,
+    edge_flows: Vec<(T, T, W)>,
+    source_side: Vec<T>,
+    cut_edges: Vec<(T, T, W)>
+}
+
+impl<T: Eq+Copy, W: Copy+Add<Output=W>+Default> MaxFlow<T, W> {
+    /// the total flow leaving the source
+    pub fn value(&self) -> W {
+        self.value
+    }
+
+    /// the flow sent along every edge of the graph as (from, to, flow), parallel edges listed separately
+    pub fn edge_flows(&self) -> &[(T, T, W)] {
+        &self.edge_flows
+    }
+
+    /// returns the total flow sent along every edge from from to to
+    pub fn flow_between(&self, from: T, to: T) -> W {
+        self.edge_flows.iter()
+            .filter(|&&(a, b, _)| a == from && b == to)
+            .fold(W::default(), |total, &(_, _, flow)| total + flow)
+    }
+
+    /// the nodes on the source side of a minimum cut, these are the nodes the source
+    /// can still reach through edges with capacity left over
+    pub fn min_cut(&self) -> &[T] {
+        &self.source_side
+    }
+
+    /// the edges crossing the minimum cut as (from, to, capacity), their capacities add up to value()
+    pub fn cut_edges(&self) -> &[(T, T, W)] {
+        &self.cut_edges
+    }
+}
+
+/* Residual network over dense node indices.
+
+Arcs are stored in pairs, so arc ^ 1 is always the reverse of arc. Even arcs
+are the graph's edges and start with their capacity left, odd arcs are their
+reverses and start with nothing left. */
+struct FlowNetwork<T, W> {
+    ids: Vec<T>,
+    arcs_from: Vec<Vec<usize>>,
+    arc_to: Vec<usize>,
+    residual: Vec<W>,
+    capacity: Vec<W>
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy+Add<Output=W>+Sub<Output=W>+Default> FlowNetwork<T, W> {
+    fn new<N, E>(graph: &Graph<T, W, N, E>) -> (Self, HashMap<T, usize>) {
+        let (ids, index) = graph.index_nodes();
+        let mut network = FlowNetwork {
+            arcs_from: vec![Vec::new(); ids.len()],
+            ids,
+            arc_to: Vec::new(),
+            residual: Vec::new(),
+            capacity: Vec::new()
+        };
+        for edge in graph.oriented_edges() {
+            network.add_arc(index[&edge.node_from], index[&edge.node_to], edge.weight);
+        }
+        (network, index)
+    }
+
+    fn add_arc(&mut self, from: usize, to: usize, capacity: W) {
+        self.arcs_from[from].push(self.arc_to.len());
+        self.arc_to.push(to);
+        self.residual.push(capacity);
+        self.capacity.push(capacity);
+        self.arcs_from[to].push(self.arc_to.len());
+        self.arc_to.push(from);
+        self.residual.push(W::default());
+        self.capacity.push(W::default());
+    }
+
+    fn has_room(&self, arc: usize) -> bool {
+        self.residual[arc] > W::default()
+    }
+
+    fn push(&mut self, arc: usize, amount: W) {
+        self.residual[arc] = self.residual[arc] - amount;
+        self.residual[arc ^ 1] = self.residual[arc ^ 1] + amount;
+    }
+
+    // sends the bottleneck amount along a path of arcs, returning the amount sent
+    fn augment(&mut self, path: &[usize]) -> W {
+        let amount = path.iter().map(|&arc| self.residual[arc]).min().unwrap_or_default();
+        for &arc in path {
+            self.push(arc, amount);
+        }
+        amount
+    }
+
+    // breadth first distance in arcs from source through arcs with room left,
+    // None for unreachable nodes, also records the arc used to reach each node
+    fn levels(&self, source: usize) -> (Vec<Option<usize>>, Vec<Option<usize>>) {
+        let mut level = vec![None; self.ids.len()];
+        let mut via = vec![None; self.ids.len()];
+        let mut queue = Queue::new();
+        level[source] = Some(0);
+        queue.push(source);
+        while let Some(node) = queue.pop() {
+            let depth = level[node].unwrap() + 1;
+            for &arc in &self.arcs_from[node] {
+                let next = self.arc_to[arc];
+                if level[next].is_none() && self.has_room(arc) {
+                    level[next] = Some(depth);
+                    via[next] = Some(arc);
+                    queue.push(next);
+                }
+            }
+        }
+        (level, via)
+    }
+
+    fn edmonds_karp(&mut self, source: usize, sink: usize) -> W {
+        let mut total = W::default();
+        loop {
+            let (level, via) = self.levels(source);
+            if level[sink].is_none() {
+                return total;
+            }
+            let mut path = Vec::new();
+            let mut node = sink;
+            while let Some(arc) = via[node] {
+                path.push(arc);
+                node = self.arc_to[arc ^ 1];
+            }
+            total = total + self.augment(&path);
+        }
+    }
+
+    fn dinic(&mut self, source: usize, sink: usize) -> W {
+        let mut total = W::default();
+        loop {
+            let (level, _) = self.levels(source);
+            if level[sink].is_none() {
+                return total;
+            }
+            // index of the next arc worth trying out of each node during this phase
+            let mut next_arc = vec![0; self.ids.len()];
+            let mut path: Vec<usize> = Vec::new();
+            let mut node = source;
+            loop {
+                if node == sink {
+                    total = total + self.augment(&path);
+                    path.clear();
+                    node = source;
+                    continue;
+                }
+                let mut advance = None;
+                while next_arc[node] < self.arcs_from[node].len() {
+                    let arc = self.arcs_from[node][next_arc[node]];
+                    let next = self.arc_to[arc];
+                    if self.has_room(arc) && level[next] == level[node].map(|l| l + 1) {
+                        advance = Some(arc);
+                        break;
+                    }
+                    next_arc[node] += 1;
+                }
+                match advance {
+                    Some(arc) => {
+                        path.push(arc);
+                        node = self.arc_to[arc];
+                    },
+                    None => match path.pop() {
+                        // dead end, back up and stop trying the arc that led here
+                        Some(arc) => {
+                            node = self.arc_to[arc ^ 1];
+                            next_arc[node] += 1;
+                        },
+                        None => break
+                    }
+                }
+            }
+        }
+    }
+
+    fn into_result<N, E>(self, graph: &Graph<T, W, N, E>, index: &HashMap<T, usize>, source: usize, value: W) -> MaxFlow<T, W> {
+        // arcs were added in the same order the edges are walked here
+        let mut edge_flows = Vec::new();
+        for (i, edge) in graph.oriented_edges().enumerate() {
+            let arc = 2 * i;
+            edge_flows.push((edge.node_from, edge.node_to, self.capacity[arc] - self.residual[arc]));
+        }
+
+        let (level, _) = self.levels(source);
+        let source_side = self.ids.iter().filter(|id| level[index[id]].is_some()).copied().collect();
+        let mut cut_edges = Vec::new();
+        for edge in graph.oriented_edges() {
+            if level[index[&edge.node_from]].is_some() && level[index[&edge.node_to]].is_none() {
+                cut_edges.push((edge.node_from, edge.node_to, edge.weight));
+            }
+        }
+        MaxFlow {
+            value,
+            edge_flows,
+            source_side,
+            cut_edges
+        }
+    }
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy+Add<Output=W>+Sub<Output=W>+Default, N, E> Graph<T, W, N, E> {
+    /// Finds the maximum flow from source to sink with Dinic's algorithm, treating edge weights
+    /// as capacities, which are expected to be non-negative. Each stored edge is an arc on its
+    /// own, so every undirected edge can carry up to its capacity in each direction.
+    /// A source equal to the sink gets zero flow.
+    /// Returns an error if source or sink is not a node in the graph.
+    pub fn max_flow(&self, source: T, sink: T) -> Result<MaxFlow<T, W>, GraphError<T>> {
+        self.check_node(source)?;
+        self.check_node(sink)?;
+        let (mut network, index) = FlowNetwork::new(self);
+        let (from, to) = (index[&source], index[&sink]);
+        let value = if from == to { W::default() } else { network.dinic(from, to) };
+        Ok(network.into_result(self, &index, from, value))
+    }
+
+    /// Finds the maximum flow from source to sink with the simpler Edmonds-Karp algorithm,
+    /// mostly useful to check max_flow against. Same conventions as max_flow.
+    /// Returns an error if source or sink is not a node in the graph.
+    pub fn max_flow_edmonds_karp(&self, source: T, sink: T) -> Result<MaxFlow<T, W>, GraphError<T>> {
+        self.check_node(source)?;
+        self.check_node(sink)?;
+        let (mut network, index) = FlowNetwork::new(self);
+        let (from, to) = (index[&source], index[&sink]);
+        let value = if from == to { W::default() } else { network.edmonds_karp(from, to) };
+        Ok(network.into_result(self, &index, from, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init() -> Graph<&'static str, i32> {
+        let mut graph = Graph::new();
+        for id in ["s", "v1", "v2", "v3", "v4", "t"] {
+            graph.add_node(id);
+        }
+        graph.add_edge("s", "v1", 16).unwrap();
+        graph.add_edge("s", "v2", 13).unwrap();
+        graph.add_edge("v2", "v1", 4).unwrap();
+        graph.add_edge("v1", "v3", 12).unwrap();
+        graph.add_edge("v3", "v2", 9).unwrap();
+        graph.add_edge("v2", "v4", 14).unwrap();
+        graph.add_edge("v4", "v3", 7).unwrap();
+        graph.add_edge("v3", "t", 20).unwrap();
+        graph.add_edge("v4", "t", 4).unwrap();
+        graph
+    }
+
+    fn assert_valid_flow(graph: &Graph<&'static str, i32>, flow: &MaxFlow<&'static str, i32>, source: &str, sink: &str) {
+        let mut net = HashMap::new();
+        for &(from, to, amount) in flow.edge_flows() {
+            let capacity = graph.get_edges_from(from).unwrap().iter()
+                .filter(|&&(id, _)| id == to)
+                .map(|&(_, weight)| weight)
+                .max()
+                .unwrap();
+            assert!(amount >= 0 && amount <= capacity);
+            *net.entry(from).or_insert(0) -= amount;
+            *net.entry(to).or_insert(0) += amount;
+        }
+        for (id, balance) in net {
+            if id == source {
+                assert_eq!(-balance, flow.value());
+            } else if id == sink {
+                assert_eq!(balance, flow.value());
+            } else {
+                assert_eq!(balance, 0);
+            }
+        }
+        let cut = flow.cut_edges().iter().map(|e| e.2).sum::<i32>();
+        assert_eq!(cut, flow.value());
+    }
+
+    #[test]
+    fn dinic() {
+        let graph = init();
+        let flow = graph.max_flow("s", "t").unwrap();
+        assert_eq!(flow.value(), 23);
+        assert_valid_flow(&graph, &flow, "s", "t");
+        let mut cut = flow.min_cut().to_vec();
+        cut.sort();
+        assert_eq!(cut, vec!["s", "v1", "v2", "v4"]);
+        assert_eq!(flow.flow_between("v3", "t"), 19);
+    }
+
+    #[test]
+    fn edmonds_karp_agrees() {
+        let graph = init();
+        let flow = graph.max_flow_edmonds_karp("s", "t").unwrap();
+        assert_eq!(flow.value(), 23);
+        assert_valid_flow(&graph, &flow, "s", "t");
+        for (a, b) in [("v1", "t"), ("s", "v3"), ("v2", "v3"), ("t", "s")] {
+            assert_eq!(graph.max_flow(a, b).unwrap().value(), graph.max_flow_edmonds_karp(a, b).unwrap().value());
+        }
+    }
+
+    #[test]
+    fn disconnected_sink() {
+        let mut graph = init();
+        graph.add_node("x");
+        let flow = graph.max_flow("s", "x").unwrap();
+        assert_eq!(flow.value(), 0);
+        assert_eq!(flow.min_cut().len(), 6);
+        assert!(flow.cut_edges().is_empty());
+        assert_eq!(graph.max_flow("s", "s").unwrap().value(), 0);
+        assert_eq!(graph.max_flow("s", "y").unwrap_err(), GraphError::NodeNotFound("y"));
+    }
+
+    #[test]
+    fn parallel_and_undirected_edges() {
+        let mut graph = Graph::new_undirected();
+        for id in 0..4 {
+            graph.add_node(id);
+        }
+        graph.add_edge(0, 1, 3).unwrap();
+        graph.add_edge(0, 1, 2).unwrap();
+        graph.add_edge(1, 2, 4).unwrap();
+        graph.add_edge(3, 2, 6).unwrap();
+        graph.add_edge(0, 3, 1).unwrap();
+        assert_eq!(graph.max_flow(0, 2).unwrap().value(), 5);
+        assert_eq!(graph.max_flow(2, 0).unwrap().value(), 5);
+        assert_eq!(graph.max_flow_edmonds_karp(2, 0).unwrap().value(), 5);
+    }
+}
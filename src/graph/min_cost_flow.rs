@@ -0,0 +1,424 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::{Add, Mul, Sub};
+
+use crate::heap::Heap;
+use super::{Candidate, Graph, NegativeCycle, ShortestPathError};
+use super::bellman_ford::predecessor_cycle;
+
+/// Edge payload for minimum cost flow, the most the edge can carry and the cost of
+/// sending one unit along it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FlowEdge<F> {
+    capacity: F,
+    cost: F
+}
+
+impl<F: Copy> FlowEdge<F> {
+    /// Constructs a payload for an edge carrying up to capacity at cost per unit
+    pub fn new(capacity: F, cost: F) -> Self {
+        FlowEdge {
+            capacity,
+            cost
+        }
+    }
+
+    /// the most flow the edge can carry
+    pub fn capacity(&self) -> F {
+        self.capacity
+    }
+
+    /// the cost of each unit of flow sent along the edge
+    pub fn cost(&self) -> F {
+        self.cost
+    }
+}
+
+/// A flow from source to sink that is the cheapest way to send its value
+#[derive(Clone, Debug)]
+pub struct MinCostFlow<T, F> {
+    value: F,
+    cost: F,
+    edge_flows: Vec<(T, T, F)>
+}
+
+impl<T: Eq+Copy, F: Copy+Add<Output=F>+Default> MinCostFlow<T, F> {
+    /// the total flow leaving the source
+    pub fn value(&self) -> F {
+        self.value
+    }
+
+    /// the total cost of the flow, every edge's flow times its cost added up
+    pub fn cost(&self) -> F {
+        self.cost
+    }
+
+    /// the flow sent along every edge of the graph as (from, to, flow), parallel edges listed separately
+    pub fn edge_flows(&self) -> &[(T, T, F)] {
+        &self.edge_flows
+    }
+
+    /// returns the total flow sent along every edge from from to to
+    pub fn flow_between(&self, from: T, to: T) -> F {
+        self.edge_flows.iter()
+            .filter(|&&(a, b, _)| a == from && b == to)
+            .fold(F::default(), |total, &(_, _, flow)| total + flow)
+    }
+}
+
+/* Residual network with costs over dense node indices.
+
+Arcs are stored in pairs like the max flow network, arc ^ 1 is the reverse of
+arc. Even arcs are the graph's edges, odd arcs undo flow and pay back its cost,
+so their cost is negative. Each node keeps a potential that makes every arc
+with room left cost zero or more once adjusted by the potentials at its ends,
+which lets Dijkstra find the cheapest augmenting paths. */
+struct CostNetwork<F> {
+    arcs_from: Vec<Vec<usize>>,
+    arc_to: Vec<usize>,
+    residual: Vec<F>,
+    capacity: Vec<F>,
+    cost: Vec<F>,
+    potential: Vec<F>
+}
+
+impl<F: Ord+Copy+Add<Output=F>+Sub<Output=F>+Mul<Output=F>+Default> CostNetwork<F> {
+    fn new(nodes: usize) -> Self {
+        CostNetwork {
+            arcs_from: vec![Vec::new(); nodes],
+            arc_to: Vec::new(),
+            residual: Vec::new(),
+            capacity: Vec::new(),
+            cost: Vec::new(),
+            potential: vec![F::default(); nodes]
+        }
+    }
+
+    fn add_arc(&mut self, from: usize, to: usize, capacity: F, cost: F) {
+        self.arcs_from[from].push(self.arc_to.len());
+        self.arc_to.push(to);
+        self.residual.push(capacity);
+        self.capacity.push(capacity);
+        self.cost.push(cost);
+        self.arcs_from[to].push(self.arc_to.len());
+        self.arc_to.push(from);
+        self.residual.push(F::default());
+        self.capacity.push(F::default());
+        self.cost.push(F::default() - cost);
+    }
+
+    // Bellman-Ford over the arcs with room from a virtual source joined to every node, so the
+    // potentials start out right even when some costs are negative. Returns the node indices
+    // of a cycle of arcs whose costs add up to less than zero if there is one.
+    fn initial_potentials(&mut self) -> Result<(), Vec<usize>> {
+        let zero = F::default();
+        if (0..self.cost.len()).step_by(2).all(|arc| self.residual[arc] <= zero || self.cost[arc] >= zero) {
+            return Ok(());
+        }
+        let nodes = self.arcs_from.len();
+        let mut predecessors = HashMap::new();
+        // the virtual source makes for one extra node, so one extra round
+        for round in 0..=nodes {
+            let mut changed = None;
+            for arc in 0..self.arc_to.len() {
+                if self.residual[arc] <= zero {
+                    continue;
+                }
+                let (from, to) = (self.arc_to[arc ^ 1], self.arc_to[arc]);
+                let next = self.potential[from] + self.cost[arc];
+                if next < self.potential[to] {
+                    self.potential[to] = next;
+                    predecessors.insert(to, from);
+                    changed = Some(to);
+                }
+            }
+            match changed {
+                None => return Ok(()),
+                Some(node) if round == nodes => {
+                    return Err(predecessor_cycle(node, &predecessors).expect("A potential dropped in every round, so the predecessors must loop"));
+                },
+                Some(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    // cost of arc adjusted by the potentials at its ends, never negative for arcs with room
+    fn reduced_cost(&self, arc: usize) -> F {
+        let from = self.arc_to[arc ^ 1];
+        self.cost[arc] + self.potential[from] - self.potential[self.arc_to[arc]]
+    }
+
+    // Dijkstra by reduced cost through arcs with room left, returning the arc used to
+    // reach each node and the reduced distance to it, None for unreachable nodes
+    fn cheapest_arcs(&self, source: usize) -> (Vec<Option<usize>>, Vec<Option<F>>) {
+        let mut distance: Vec<Option<F>> = vec![None; self.arcs_from.len()];
+        let mut via = vec![None; self.arcs_from.len()];
+        let mut open = Heap::new();
+        distance[source] = Some(F::default());
+        open.push(Reverse(Candidate::new(F::default(), source)));
+        while let Some(Reverse(Candidate { priority: dist, node })) = open.pop() {
+            if distance[node].is_some_and(|known| dist > known) {
+                continue;
+            }
+            for &arc in &self.arcs_from[node] {
+                if self.residual[arc] <= F::default() {
+                    continue;
+                }
+                let next = self.arc_to[arc];
+                let next_dist = dist + self.reduced_cost(arc);
+                let improved = match distance[next] {
+                    Some(known) => next_dist < known,
+                    None => true
+                };
+                if improved {
+                    distance[next] = Some(next_dist);
+                    via[next] = Some(arc);
+                    open.push(Reverse(Candidate::new(next_dist, next)));
+                }
+            }
+        }
+        (via, distance)
+    }
+
+    // sends flow along cheapest paths until limit is reached or the sink is cut off,
+    // returning the flow sent and its cost
+    fn successive_shortest_paths(&mut self, source: usize, sink: usize, limit: Option<F>) -> (F, F) {
+        let mut sent = F::default();
+        let mut total_cost = F::default();
+        while limit.map(|limit| sent < limit).unwrap_or(true) {
+            let (via, distance) = self.cheapest_arcs(source);
+            if distance[sink].is_none() {
+                break;
+            }
+            // nodes left unreachable can't become reachable again, so their potentials can stay
+            for (node, dist) in distance.into_iter().enumerate() {
+                if let Some(dist) = dist {
+                    self.potential[node] = self.potential[node] + dist;
+                }
+            }
+            let mut path = Vec::new();
+            let mut node = sink;
+            while let Some(arc) = via[node] {
+                path.push(arc);
+                node = self.arc_to[arc ^ 1];
+            }
+            let mut amount = path.iter().map(|&arc| self.residual[arc]).min().unwrap_or_default();
+            if let Some(limit) = limit {
+                amount = amount.min(limit - sent);
+            }
+            for &arc in &path {
+                self.residual[arc] = self.residual[arc] - amount;
+                self.residual[arc ^ 1] = self.residual[arc ^ 1] + amount;
+                total_cost = total_cost + amount * self.cost[arc];
+            }
+            sent = sent + amount;
+        }
+        (sent, total_cost)
+    }
+}
+
+/* Minimum cost flow by successive shortest paths. Each round sends as much as the
+cheapest remaining path from source to sink allows, so the flow stays as cheap as
+possible for its value the whole way. Costs may be negative as long as no cycle of
+edges with capacity adds up to a negative cost, and F has to hold negative values,
+so use a signed type. Each stored edge is an arc on its own, so every undirected
+edge can carry up to its capacity in each direction, and a negative undirected
+edge is a negative cycle on its own. */
+impl<T: Hash+Eq+Copy, W: Ord+Copy, N, F: Ord+Copy+Add<Output=F>+Sub<Output=F>+Mul<Output=F>+Default> Graph<T, W, N, FlowEdge<F>> {
+    /// Finds the cheapest way to send as much flow as possible from source to sink
+    /// A source equal to the sink gets zero flow.
+    /// Returns an error if source or sink is not a node in the graph, or if there's a cycle
+    /// with a negative total cost.
+    pub fn min_cost_max_flow(&self, source: T, sink: T) -> Result<MinCostFlow<T, F>, ShortestPathError<T>> {
+        self.solve_min_cost_flow(source, sink, None)
+    }
+
+    /// Finds the cheapest way to send up to limit flow from source to sink, less if the
+    /// network can't carry that much
+    /// Returns an error if source or sink is not a node in the graph, or if there's a cycle
+    /// with a negative total cost.
+    pub fn min_cost_flow(&self, source: T, sink: T, limit: F) -> Result<MinCostFlow<T, F>, ShortestPathError<T>> {
+        self.solve_min_cost_flow(source, sink, Some(limit))
+    }
+
+    fn solve_min_cost_flow(&self, source: T, sink: T, limit: Option<F>) -> Result<MinCostFlow<T, F>, ShortestPathError<T>> {
+        self.check_node(source)?;
+        self.check_node(sink)?;
+        let (ids, index) = self.index_nodes();
+        let mut network = CostNetwork::new(ids.len());
+        for edge in self.oriented_edges() {
+            network.add_arc(index[&edge.node_from], index[&edge.node_to], edge.data.capacity, edge.data.cost);
+        }
+        if let Err(cycle) = network.initial_potentials() {
+            let nodes = cycle.into_iter().map(|i| ids[i]).collect();
+            return Err(ShortestPathError::NegativeCycle(NegativeCycle::new(nodes)));
+        }
+        let (from, to) = (index[&source], index[&sink]);
+        let (value, cost) = if from == to {
+            (F::default(), F::default())
+        } else {
+            network.successive_shortest_paths(from, to, limit)
+        };
+        // arcs were added in the same order the edges are walked here
+        let edge_flows = self.oriented_edges().enumerate().map(|(i, edge)| {
+            let arc = 2 * i;
+            (edge.node_from, edge.node_to, network.capacity[arc] - network.residual[arc])
+        }).collect();
+        Ok(MinCostFlow {
+            value,
+            cost,
+            edge_flows
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use super::super::{Direction, Duplicates, GraphError};
+
+    type Network = Graph<&'static str, i32, (), FlowEdge<i64>>;
+
+    // two plants s1 and s2 behind a super source, two depots and one market t
+    fn transport() -> Network {
+        let mut graph = Graph::with_payloads(Direction::Directed, Duplicates::Allow);
+        for id in ["s", "s1", "s2", "d1", "d2", "t"] {
+            graph.add_node(id);
+        }
+        for &(from, to, capacity, cost) in [("s", "s1", 10, 0), ("s", "s2", 6, 0), ("s1", "d1", 4, 2), ("s1", "d2", 8, 5),
+                                            ("s2", "d1", 5, 1), ("s2", "d2", 3, 3), ("d1", "t", 7, 1), ("d2", "t", 9, 2)].iter() {
+            graph.add_edge_with(from, to, 0, FlowEdge::new(capacity, cost)).unwrap();
+        }
+        graph
+    }
+
+    fn assert_valid_flow(graph: &Network, flow: &MinCostFlow<&'static str, i64>, source: &str, sink: &str) {
+        let mut net = HashMap::new();
+        let mut cost = 0;
+        let capacities: Vec<(i64, i64)> = graph.oriented_edges().map(|edge| (edge.data.capacity(), edge.data.cost())).collect();
+        for (&(from, to, amount), &(capacity, unit_cost)) in flow.edge_flows().iter().zip(&capacities) {
+            assert!(amount >= 0 && amount <= capacity);
+            cost += amount * unit_cost;
+            *net.entry(from).or_insert(0) -= amount;
+            *net.entry(to).or_insert(0) += amount;
+        }
+        for (id, balance) in net {
+            if id == source {
+                assert_eq!(-balance, flow.value());
+            } else if id == sink {
+                assert_eq!(balance, flow.value());
+            } else {
+                assert_eq!(balance, 0);
+            }
+        }
+        assert_eq!(cost, flow.cost());
+    }
+
+    #[test]
+    fn cheapest_maximum_flow() {
+        let graph = transport();
+        let flow = graph.min_cost_max_flow("s", "t").unwrap();
+        assert_valid_flow(&graph, &flow, "s", "t");
+        assert_eq!(flow.value(), 16);
+        // s1 fills its cheap route to d1, and s2 splits evenly so d2 takes as little from s1 as it can
+        assert_eq!(flow.cost(), 4 * 2 + 6 * 5 + 3 + 3 * 3 + 7 + 9 * 2);
+        assert_eq!(flow.flow_between("s1", "d1"), 4);
+        assert_eq!(flow.flow_between("s2", "d1"), 3);
+        assert_eq!(flow.flow_between("s2", "d2"), 3);
+    }
+
+    #[test]
+    fn limited_flow_takes_cheapest_routes() {
+        let graph = transport();
+        let flow = graph.min_cost_flow("s", "t", 6).unwrap();
+        assert_valid_flow(&graph, &flow, "s", "t");
+        assert_eq!((flow.value(), flow.cost()), (6, 5 * 2 + 3));
+        let flow = graph.min_cost_flow("s", "t", 100).unwrap();
+        assert_eq!(flow.value(), 16);
+        assert_eq!(graph.min_cost_flow("s", "t", 0).unwrap().cost(), 0);
+    }
+
+    #[test]
+    fn rerouting_through_reverse_arcs() {
+        // the cheapest first path a-b-d blocks both later paths unless flow on a-b is undone
+        let mut graph: Graph<char, i32, (), FlowEdge<i64>> = Graph::with_payloads(Direction::Directed, Duplicates::Allow);
+        for id in "sabt".chars() {
+            graph.add_node(id);
+        }
+        for &(from, to, capacity, cost) in [('s', 'a', 1, 1), ('s', 'b', 1, 3), ('a', 'b', 1, 1),
+                                            ('a', 't', 1, 3), ('b', 't', 1, 1)].iter() {
+            graph.add_edge_with(from, to, 0, FlowEdge::new(capacity, cost)).unwrap();
+        }
+        let flow = graph.min_cost_max_flow('s', 't').unwrap();
+        assert_eq!((flow.value(), flow.cost()), (2, 8));
+        assert_eq!(flow.flow_between('a', 'b'), 0);
+    }
+
+    #[test]
+    fn agrees_with_max_flow() {
+        let mut graph: Graph<usize, i64, (), FlowEdge<i64>> = Graph::with_payloads(Direction::Undirected, Duplicates::Allow);
+        let mut plain: Graph<usize, i64> = Graph::with_mode(Direction::Undirected, Duplicates::Allow);
+        for id in 0..6 {
+            graph.add_node(id);
+            plain.add_node(id);
+        }
+        for &(from, to, capacity) in [(0, 1, 4), (0, 2, 3), (1, 2, 2), (1, 3, 5), (2, 4, 6), (3, 4, 1), (3, 5, 3), (4, 5, 7), (0, 1, 2)].iter() {
+            graph.add_edge_with(from, to, 0, FlowEdge::new(capacity, (from + to) as i64)).unwrap();
+            plain.add_edge(from, to, capacity).unwrap();
+        }
+        for &(source, sink) in [(0, 5), (5, 0), (2, 3)].iter() {
+            let flow = graph.min_cost_max_flow(source, sink).unwrap();
+            assert_eq!(flow.value(), plain.max_flow(source, sink).unwrap().value());
+        }
+    }
+
+    #[test]
+    fn edge_cases() {
+        let mut graph = transport();
+        graph.add_node("x");
+        let flow = graph.min_cost_max_flow("s", "x").unwrap();
+        assert_eq!((flow.value(), flow.cost()), (0, 0));
+        assert!(flow.edge_flows().iter().all(|&(_, _, amount)| amount == 0));
+        assert_eq!(graph.min_cost_max_flow("s", "s").unwrap().value(), 0);
+        assert_eq!(graph.min_cost_max_flow("s", "y").err(), Some(ShortestPathError::Graph(GraphError::NodeNotFound("y"))));
+    }
+
+    fn directed(edges: &[(i32, i32, i64, i64)]) -> Graph<i32, i32, (), FlowEdge<i64>> {
+        let mut graph = Graph::with_payloads(Direction::Directed, Duplicates::Allow);
+        for &(from, to, capacity, cost) in edges {
+            graph.add_node(from);
+            graph.add_node(to);
+            graph.add_edge_with(from, to, 0, FlowEdge::new(capacity, cost)).unwrap();
+        }
+        graph
+    }
+
+    #[test]
+    fn negative_costs() {
+        // the detour through 2 is cheaper than going straight to 3
+        let graph = directed(&[(0, 1, 2, 1), (1, 2, 1, -4), (2, 3, 1, 1), (1, 3, 2, 1)]);
+        let flow = graph.min_cost_max_flow(0, 3).unwrap();
+        assert_eq!((flow.value(), flow.cost()), (2, -2 + 2));
+        assert_eq!(flow.flow_between(1, 2), 1);
+        assert_eq!(graph.min_cost_flow(0, 3, 1).unwrap().cost(), -2);
+    }
+
+    #[test]
+    fn negative_cycles_are_rejected() {
+        let graph = directed(&[(0, 1, 1, 1), (1, 2, 1, -4), (2, 1, 1, 1), (2, 3, 1, 1)]);
+        match graph.min_cost_max_flow(0, 3) {
+            Err(ShortestPathError::NegativeCycle(cycle)) => {
+                let mut nodes = cycle.nodes().to_vec();
+                nodes.sort_unstable();
+                assert_eq!(nodes, vec![1, 2]);
+            },
+            other => panic!("expected a negative cycle, got {:?}", other.map(|flow| flow.cost()))
+        }
+        // a cycle without capacity can't carry flow, so it doesn't count
+        let graph = directed(&[(0, 1, 1, 1), (1, 2, 1, -4), (2, 1, 0, 1), (2, 3, 1, 1)]);
+        assert_eq!(graph.min_cost_max_flow(0, 3).unwrap().cost(), -2);
+    }
+}
@@ -0,0 +1,170 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::ops::Add;
+
+use crate::heap::Heap;
+use crate::union_find::UnionFind;
+use super::{Candidate, Direction, Graph, GraphError};
+
+/// Edges picked for a minimum spanning forest, one tree per connected component
+#[derive(Clone, Debug)]
+pub struct SpanningForest<T, W> {
+    edges: Vec<(T, T, W)>,
+    total_weight: W,
+    trees: usize
+}
+
+impl<T, W: Copy> SpanningForest<T, W> {
+    /// the chosen edges as (from, to, weight), in the order they were picked
+    pub fn edges(&self) -> &[(T, T, W)] {
+        &self.edges
+    }
+
+    /// the sum of the chosen edge weights
+    pub fn total_weight(&self) -> W {
+        self.total_weight
+    }
+
+    /// returns the number of trees, one for each connected component
+    pub fn tree_count(&self) -> usize {
+        self.trees
+    }
+
+    /// checks to see if the forest is a single tree covering the whole graph
+    pub fn is_spanning_tree(&self) -> bool {
+        self.trees <= 1
+    }
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy+Add<Output=W>+Default, N, E> Graph<T, W, N, E> {
+    /// Builds a minimum spanning forest with Kruskal's algorithm
+    /// Returns an error if the graph is directed
+    pub fn kruskal(&self) -> Result<SpanningForest<T, W>, GraphError<T>> {
+        self.require_direction(Direction::Undirected)?;
+        let (_, index) = self.index_nodes();
+        let mut candidates: Vec<(T, T, W)> = self.edges.iter()
+            .flatten()
+            .map(|edge| (edge.node_from, edge.node_to, edge.weight))
+            .collect();
+        candidates.sort_by_key(|&(_, _, weight)| weight);
+
+        let mut sets = UnionFind::new(self.nodes.len());
+        let mut chosen = Vec::new();
+        let mut total_weight = W::default();
+        for (from, to, weight) in candidates {
+            if sets.union(index[&from], index[&to]) {
+                chosen.push((from, to, weight));
+                total_weight = total_weight + weight;
+            }
+        }
+        Ok(SpanningForest {
+            edges: chosen,
+            total_weight,
+            trees: sets.set_count()
+        })
+    }
+
+    /// Builds a minimum spanning forest with Prim's algorithm, growing each tree from a min ordered Heap
+    /// Returns an error if the graph is directed
+    pub fn prim(&self) -> Result<SpanningForest<T, W>, GraphError<T>> {
+        self.require_direction(Direction::Undirected)?;
+        let mut in_tree = HashSet::with_capacity(self.nodes.len());
+        let mut chosen = Vec::new();
+        let mut total_weight = W::default();
+        let mut trees = 0;
+        let mut frontier = Heap::new();
+
+        for &root in self.nodes.keys() {
+            if !in_tree.insert(root) {
+                continue;
+            }
+            trees += 1;
+            self.push_edges_from(root, &in_tree, &mut frontier);
+            while let Some(Reverse(Candidate { priority: weight, node: (from, to) })) = frontier.pop() {
+                if !in_tree.insert(to) {
+                    continue;
+                }
+                chosen.push((from, to, weight));
+                total_weight = total_weight + weight;
+                self.push_edges_from(to, &in_tree, &mut frontier);
+            }
+        }
+        Ok(SpanningForest {
+            edges: chosen,
+            total_weight,
+            trees
+        })
+    }
+
+    fn push_edges_from(&self, id: T, in_tree: &HashSet<T>, frontier: &mut Heap<Reverse<Candidate<W, (T, T)>>>) {
+        for edge in self.out_edges(id) {
+            if !in_tree.contains(&edge.node_to) {
+                frontier.push(Reverse(Candidate::new(edge.weight, (id, edge.node_to))));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn init() -> Graph<char, u32> {
+        let mut graph = Graph::new_undirected();
+        for id in "abcdefg".chars() {
+            graph.add_node(id);
+        }
+        graph.add_edge('a', 'b', 7).unwrap();
+        graph.add_edge('a', 'd', 5).unwrap();
+        graph.add_edge('b', 'c', 8).unwrap();
+        graph.add_edge('b', 'd', 9).unwrap();
+        graph.add_edge('b', 'e', 7).unwrap();
+        graph.add_edge('c', 'e', 5).unwrap();
+        graph.add_edge('d', 'e', 15).unwrap();
+        graph.add_edge('d', 'f', 6).unwrap();
+        graph.add_edge('e', 'f', 8).unwrap();
+        graph.add_edge('e', 'g', 9).unwrap();
+        graph.add_edge('f', 'g', 11).unwrap();
+        graph
+    }
+
+    fn assert_forest(forest: &SpanningForest<char, u32>, total: u32, trees: usize, edge_count: usize) {
+        assert_eq!(forest.total_weight(), total);
+        assert_eq!(forest.tree_count(), trees);
+        assert_eq!(forest.edges().len(), edge_count);
+        assert_eq!(forest.edges().iter().map(|e| e.2).sum::<u32>(), total);
+    }
+
+    #[test]
+    fn spanning_tree() {
+        let graph = init();
+        let kruskal = graph.kruskal().unwrap();
+        assert_forest(&kruskal, 39, 1, 6);
+        assert!(kruskal.is_spanning_tree());
+        assert_forest(&graph.prim().unwrap(), 39, 1, 6);
+    }
+
+    #[test]
+    fn spanning_forest_of_disconnected_graph() {
+        let mut graph = init();
+        for id in "xyz".chars() {
+            graph.add_node(id);
+        }
+        graph.add_edge('x', 'y', 2).unwrap();
+        graph.add_edge('x', 'y', 1).unwrap();
+        graph.add_edge('y', 'y', 0).unwrap();
+        let kruskal = graph.kruskal().unwrap();
+        assert_forest(&kruskal, 40, 3, 7);
+        assert!(!kruskal.is_spanning_tree());
+        assert_forest(&graph.prim().unwrap(), 40, 3, 7);
+    }
+
+    #[test]
+    fn directed_graph_is_rejected() {
+        let mut graph = Graph::<i32, i32>::new();
+        graph.add_node(1);
+        assert_eq!(graph.kruskal().err(), Some(GraphError::UnsupportedDirection(Direction::Directed)));
+        assert!(graph.prim().is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+
+use super::Graph;
+
+/// Error returned when a graph that needs to be acyclic isn't
+/// Holds one cycle in walk order, the last node has an edge back to the first
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cycle<T> {
+    nodes: Vec<T>
+}
+
+impl<T> Cycle<T> {
+    /// the nodes making up the cycle, in the order the edges visit them
+    pub fn nodes(&self) -> &[T] {
+        &self.nodes
+    }
+}
+
+impl<T: fmt::Debug> fmt::Display for Cycle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "graph contains a cycle: ")?;
+        for node in &self.nodes {
+            write!(f, "{:?} -> ", node)?;
+        }
+        match self.nodes.first() {
+            Some(first) => write!(f, "{:?}", first),
+            None => Ok(())
+        }
+    }
+}
+
+impl<T: fmt::Debug> Error for Cycle<T> {}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    InProgress,
+    Done
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy, N, E> Graph<T, W, N, E> {
+    /// Orders the nodes so every edge goes from an earlier node to a later one
+    /// Returns one offending cycle if there is no such order. Undirected edges
+    /// go both ways, so any undirected edge counts as a cycle.
+    pub fn topological_order(&self) -> Result<Vec<T>, Cycle<T>> {
+        let mut marks = HashMap::with_capacity(self.nodes.len());
+        let mut finished = Vec::with_capacity(self.nodes.len());
+        // (node, index of the next edge to look at), doubles as the current path
+        let mut path: Vec<(T, usize)> = Vec::new();
+
+        for &root in self.nodes.keys() {
+            if marks.contains_key(&root) {
+                continue;
+            }
+            marks.insert(root, Mark::InProgress);
+            path.push((root, 0));
+            while let Some(&(node, next_edge)) = path.last() {
+                let slots = self.edge_slots(node);
+                if next_edge < slots.len() {
+                    path.last_mut().unwrap().1 += 1;
+                    let child = self.edge_at(node, slots[next_edge]).node_to;
+                    match marks.get(&child) {
+                        None => {
+                            marks.insert(child, Mark::InProgress);
+                            path.push((child, 0));
+                        },
+                        Some(Mark::InProgress) => {
+                            // child is on the current path, so the path from it back to here is a cycle
+                            let start = path.iter().position(|&(id, _)| id == child).unwrap();
+                            let nodes = path[start..].iter().map(|&(id, _)| id).collect();
+                            return Err(Cycle { nodes });
+                        },
+                        Some(Mark::Done) => {}
+                    }
+                } else {
+                    marks.insert(node, Mark::Done);
+                    finished.push(node);
+                    path.pop();
+                }
+            }
+        }
+        finished.reverse();
+        Ok(finished)
+    }
+
+    /// Groups the nodes into layers using Kahn's algorithm. Every node only has
+    /// edges coming in from earlier layers, so the nodes within a layer can be
+    /// scheduled in parallel once the layers before them are done.
+    /// Returns one offending cycle if the graph isn't acyclic.
+    pub fn topological_layers(&self) -> Result<Vec<Vec<T>>, Cycle<T>> {
+        let mut in_degree: HashMap<T, usize> = self.nodes.keys().map(|&id| (id, 0)).collect();
+        for edge in self.oriented_edges() {
+            *in_degree.get_mut(&edge.node_to).unwrap() += 1;
+        }
+
+        let mut layers = Vec::new();
+        let mut layer: Vec<T> = in_degree.iter()
+            .filter(|&(_, &count)| count == 0)
+            .map(|(&id, _)| id)
+            .collect();
+        let mut placed = 0;
+        while !layer.is_empty() {
+            let mut next_layer = Vec::new();
+            for node in &layer {
+                for edge in self.out_edges(*node) {
+                    let count = in_degree.get_mut(&edge.node_to).unwrap();
+                    *count -= 1;
+                    if *count == 0 {
+                        next_layer.push(edge.node_to);
+                    }
+                }
+            }
+            placed += layer.len();
+            layers.push(layer);
+            layer = next_layer;
+        }
+
+        if placed < self.nodes.len() {
+            match self.topological_order() {
+                Err(cycle) => Err(cycle),
+                Ok(_) => unreachable!("Kahn's algorithm got stuck so there has to be a cycle")
+            }
+        } else {
+            Ok(layers)
+        }
+    }
+
+    /// checks to see if the graph has no cycles
+    pub fn is_acyclic(&self) -> bool {
+        self.topological_order().is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // shirt -> tie -> jacket, trousers -> shoes, trousers -> belt -> jacket, socks -> shoes
+    fn init() -> Graph<&'static str, u8> {
+        let mut graph = Graph::new();
+        for id in ["shirt", "tie", "jacket", "trousers", "shoes", "belt", "socks"] {
+            graph.add_node(id);
+        }
+        graph.add_edge("shirt", "tie", 1).unwrap();
+        graph.add_edge("tie", "jacket", 1).unwrap();
+        graph.add_edge("trousers", "shoes", 1).unwrap();
+        graph.add_edge("trousers", "belt", 1).unwrap();
+        graph.add_edge("belt", "jacket", 1).unwrap();
+        graph.add_edge("socks", "shoes", 1).unwrap();
+        graph
+    }
+
+    fn assert_cycle(graph: &Graph<&'static str, u8>, cycle: &Cycle<&'static str>) {
+        let nodes = cycle.nodes();
+        assert!(!nodes.is_empty());
+        for i in 0..nodes.len() {
+            assert!(graph.contains_edge(nodes[i], nodes[(i + 1) % nodes.len()]));
+        }
+    }
+
+    #[test]
+    fn order_respects_edges() {
+        let graph = init();
+        let order = graph.topological_order().unwrap();
+        assert_eq!(order.len(), 7);
+        let position = |id| order.iter().position(|&n| n == id).unwrap();
+        assert!(position("shirt") < position("tie"));
+        assert!(position("tie") < position("jacket"));
+        assert!(position("belt") < position("jacket"));
+        assert!(position("trousers") < position("belt"));
+        assert!(position("socks") < position("shoes"));
+        assert!(graph.is_acyclic());
+    }
+
+    #[test]
+    fn layers() {
+        let graph = init();
+        let mut layers = graph.topological_layers().unwrap();
+        for layer in layers.iter_mut() {
+            layer.sort();
+        }
+        assert_eq!(layers, vec![
+            vec!["shirt", "socks", "trousers"],
+            vec!["belt", "shoes", "tie"],
+            vec!["jacket"]
+        ]);
+    }
+
+    #[test]
+    fn cycle_is_reported() {
+        let mut graph = init();
+        graph.add_edge("jacket", "trousers", 1).unwrap();
+        let cycle = graph.topological_order().unwrap_err();
+        assert_cycle(&graph, &cycle);
+        assert_eq!(cycle.nodes().len(), 3);
+        let cycle = graph.topological_layers().unwrap_err();
+        assert_cycle(&graph, &cycle);
+        assert!(!graph.is_acyclic());
+    }
+
+    #[test]
+    fn self_loop_is_a_cycle() {
+        let mut graph = init();
+        graph.add_edge("socks", "socks", 1).unwrap();
+        let cycle = graph.topological_order().unwrap_err();
+        assert_eq!(cycle.nodes(), &["socks"]);
+        assert_eq!(cycle.to_string(), "graph contains a cycle: \"socks\" -> \"socks\"");
+    }
+
+    #[test]
+    fn empty_graph() {
+        let graph = Graph::<i32, i32>::new();
+        assert_eq!(graph.topological_order(), Ok(vec![]));
+        assert_eq!(graph.topological_layers(), Ok(vec![]));
+    }
+}
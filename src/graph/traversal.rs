@@ -0,0 +1,235 @@
+use std::hash::Hash;
+
+use crate::queue::Queue;
+use crate::stack::Stack;
+use super::{Graph, GraphAccess, GraphError};
+
+/// Lazy breadth first walk yielding (node, depth) pairs
+pub struct Bfs<'a, G: GraphAccess> {
+    graph: &'a G,
+    // (node index, depth)
+    queue: Queue<(usize, usize)>,
+    visited: Vec<bool>
+}
+
+/// Which point of the walk a depth first search yields a node at
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DfsOrder {
+    /// yield a node when it is first discovered
+    PreOrder,
+    /// yield a node once everything reachable from it has been walked
+    PostOrder
+}
+
+/// Lazy depth first walk yielding (node, depth) pairs, depth is the depth the node was discovered at
+pub struct Dfs<'a, G: GraphAccess> {
+    graph: &'a G,
+    // (node index, depth, the edges out of it still to look at)
+    stack: Stack<(usize, usize, G::OutEdges<'a>)>,
+    visited: Vec<bool>,
+    order: DfsOrder,
+    start: Option<(G::Node, usize)>
+}
+
+impl<'a, G: GraphAccess> Bfs<'a, G> {
+    // starts a walk from start, which has to be in the graph
+    pub(super) fn new(graph: &'a G, start: G::Node) -> Result<Self, GraphError<G::Node>> {
+        let index = graph.require_node(start)?;
+        let mut queue = Queue::new();
+        let mut visited = vec![false; graph.node_bound()];
+        queue.push((index, 0));
+        visited[index] = true;
+        Ok(Bfs {
+            graph,
+            queue,
+            visited
+        })
+    }
+}
+
+impl<'a, G: GraphAccess> Dfs<'a, G> {
+    // starts a walk from start, which has to be in the graph
+    pub(super) fn new(graph: &'a G, start: G::Node, order: DfsOrder) -> Result<Self, GraphError<G::Node>> {
+        let index = graph.require_node(start)?;
+        let mut stack = Stack::new();
+        let mut visited = vec![false; graph.node_bound()];
+        stack.push((index, 0, graph.out_edges_at(index)));
+        visited[index] = true;
+        Ok(Dfs {
+            graph,
+            stack,
+            visited,
+            order,
+            start: if order == DfsOrder::PreOrder { Some((start, 0)) } else { None }
+        })
+    }
+}
+
+// the id of a node a walk has reached
+fn id_at<G: GraphAccess>(graph: &G, index: usize) -> G::Node {
+    graph.node_at(index).expect("Walks only reach nodes in the graph")
+}
+
+impl<T: Hash+Eq+Copy, W: Ord+Copy, N, E> Graph<T, W, N, E> {
+    /// Walks the graph breadth first from start, following edges in the order they were added
+    /// Returns an error if start is not a node in the graph
+    pub fn bfs(&self, start: T) -> Result<Bfs<'_, Self>, GraphError<T>> {
+        Bfs::new(self, start)
+    }
+
+    /// Walks the graph depth first from start, yielding nodes in pre-order
+    /// Returns an error if start is not a node in the graph
+    pub fn dfs(&self, start: T) -> Result<Dfs<'_, Self>, GraphError<T>> {
+        self.dfs_with_order(start, DfsOrder::PreOrder)
+    }
+
+    /// Walks the graph depth first from start, yielding nodes in post-order
+    /// Returns an error if start is not a node in the graph
+    pub fn dfs_post_order(&self, start: T) -> Result<Dfs<'_, Self>, GraphError<T>> {
+        self.dfs_with_order(start, DfsOrder::PostOrder)
+    }
+
+    /// Walks the graph depth first from start, yielding nodes in the given order
+    /// Returns an error if start is not a node in the graph
+    pub fn dfs_with_order(&self, start: T, order: DfsOrder) -> Result<Dfs<'_, Self>, GraphError<T>> {
+        Dfs::new(self, start, order)
+    }
+}
+
+impl<'a, G: GraphAccess> Iterator for Bfs<'a, G> {
+    type Item = (G::Node, usize);
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, depth) = self.queue.pop()?;
+        for (next, _) in self.graph.out_edges_at(node) {
+            if !self.visited[next] {
+                self.visited[next] = true;
+                self.queue.push((next, depth + 1));
+            }
+        }
+        Some((id_at(self.graph, node), depth))
+    }
+}
+
+impl<'a, G: GraphAccess> Iterator for Dfs<'a, G> {
+    type Item = (G::Node, usize);
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(start) = self.start.take() {
+            return Some(start);
+        }
+        while let Some((node, depth, mut edges)) = self.stack.pop() {
+            let visited = &self.visited;
+            match edges.by_ref().map(|(next, _)| next).find(|&next| !visited[next]) {
+                Some(child) => {
+                    self.visited[child] = true;
+                    self.stack.push((node, depth, edges));
+                    self.stack.push((child, depth + 1, self.graph.out_edges_at(child)));
+                    if self.order == DfsOrder::PreOrder {
+                        return Some((id_at(self.graph, child), depth + 1));
+                    }
+                },
+                None => if self.order == DfsOrder::PostOrder {
+                    return Some((id_at(self.graph, node), depth));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //     1
+    //    / \
+    //   2   3
+    //  / \   \
+    // 4   5   6 -> 1
+    fn init() -> Graph<i32, i32> {
+        let mut graph = Graph::new();
+        for id in 1..=7 {
+            graph.add_node(id);
+        }
+        graph.add_edge(1, 2, 1).unwrap();
+        graph.add_edge(1, 3, 1).unwrap();
+        graph.add_edge(2, 4, 1).unwrap();
+        graph.add_edge(2, 5, 1).unwrap();
+        graph.add_edge(3, 6, 1).unwrap();
+        graph.add_edge(6, 1, 1).unwrap();
+        graph
+    }
+
+    #[test]
+    fn missing_start() {
+        let graph = init();
+        assert!(graph.bfs(0).is_err());
+        assert!(graph.dfs(0).is_err());
+        assert_eq!(graph.dfs_post_order(0).err(), Some(GraphError::NodeNotFound(0)));
+    }
+
+    #[test]
+    fn bfs_order_and_depth() {
+        let graph = init();
+        let walk = graph.bfs(1).unwrap().collect::<Vec<_>>();
+        assert_eq!(walk, vec![(1, 0), (2, 1), (3, 1), (4, 2), (5, 2), (6, 2)]);
+    }
+
+    #[test]
+    fn dfs_pre_order() {
+        let graph = init();
+        let walk = graph.dfs(1).unwrap().collect::<Vec<_>>();
+        assert_eq!(walk, vec![(1, 0), (2, 1), (4, 2), (5, 2), (3, 1), (6, 2)]);
+    }
+
+    #[test]
+    fn dfs_post_order() {
+        let graph = init();
+        let walk = graph.dfs_post_order(1).unwrap().collect::<Vec<_>>();
+        assert_eq!(walk, vec![(4, 2), (5, 2), (2, 1), (6, 2), (3, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn walk_from_inner_node() {
+        let graph = init();
+        let walk = graph.dfs(3).unwrap().map(|(id, _)| id).collect::<Vec<_>>();
+        assert_eq!(walk, vec![3, 6, 1, 2, 4, 5]);
+        let walk = graph.bfs(7).unwrap().collect::<Vec<_>>();
+        assert_eq!(walk, vec![(7, 0)]);
+    }
+
+    #[test]
+    fn walks_are_lazy() {
+        let graph = init();
+        let mut walk = graph.bfs(1).unwrap();
+        assert_eq!(walk.next(), Some((1, 0)));
+        assert_eq!(walk.queue.len(), 2);
+    }
+
+    #[test]
+    fn walks_after_removing_nodes() {
+        let mut graph = init();
+        graph.remove_node(2).unwrap();
+        // 8 takes the index node 2 left behind
+        graph.add_node(8);
+        assert_eq!(graph.node_bound(), 7);
+        assert_eq!(graph.node_index(8), Some(1));
+        graph.add_edge(6, 8, 1).unwrap();
+        let walk = graph.bfs(1).unwrap().collect::<Vec<_>>();
+        assert_eq!(walk, vec![(1, 0), (3, 1), (6, 2), (8, 3)]);
+        let walk = graph.dfs_post_order(3).unwrap().map(|(id, _)| id).collect::<Vec<_>>();
+        assert_eq!(walk, vec![1, 8, 6, 3]);
+    }
+
+    #[test]
+    fn long_chain() {
+        let mut graph = Graph::new();
+        for id in 0..100_000 {
+            graph.add_node(id);
+            if id > 0 {
+                graph.add_edge(id - 1, id, 1).unwrap();
+            }
+        }
+        assert_eq!(graph.dfs_post_order(0).unwrap().next(), Some((99_999, 99_999)));
+        assert_eq!(graph.bfs(0).unwrap().count(), 100_000);
+    }
+}
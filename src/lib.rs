@@ -3,4 +3,5 @@ pub mod graph;
 pub mod binary_tree;
 pub mod heap;
 pub mod queue;
-pub mod avl_tree;
\ No newline at end of file
+pub mod avl_tree;
+pub mod union_find;
\ No newline at end of file
@@ -3,46 +3,40 @@ struct Node<T> {
     next: Option<Box<Node<T>>>
 }
 
+/* First in first out queue built from two linked stacks.
+
+New items are pushed on top of back, and items are popped from the top of
+front. When front runs dry the back stack is reversed onto it, so every item
+is moved at most once and push and pop are both amortized constant time. */
 pub struct Queue<T> {
     front: Option<Box<Node<T>>>,
+    back: Option<Box<Node<T>>>,
     size: usize
 }
 
-impl<T> Node<T> {
-    fn new(data: T) -> Self {
-        Node {
-            data,
-            next: None
-        }
-    }
-
-    fn append(&mut self, data: T) {
-        if let Some(val) = &mut self.next {
-            val.append(data);
-        } else {
-            self.next = Some(Box::new(Node::new(data)));
-        }
-    }
-}
-
 impl<T> Queue<T> {
     pub fn new() -> Self {
         Queue {
             front: None,
+            back: None,
             size: 0
         }
     }
 
     pub fn push(&mut self, data: T) {
-        if let Some(val) = &mut self.front {
-            val.append(data);
-        } else {
-            self.front = Some(Box::new(Node::new(data)));
-        }
+        let prev_back = self.back.take();
+        self.back = Some(Box::new(Node { data, next: prev_back }));
         self.size += 1;
     }
 
     pub fn pop(&mut self) -> Option<T> {
+        if self.front.is_none() {
+            while let Some(mut node) = self.back.take() {
+                self.back = node.next.take();
+                node.next = self.front.take();
+                self.front = Some(node);
+            }
+        }
         if let Some(val) = self.front.take() {
             self.front = val.next;
             self.size -= 1;
@@ -51,4 +45,83 @@ impl<T> Queue<T> {
             None
         }
     }
-}
\ No newline at end of file
+
+    /// returns number of items in the queue
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    /// checks to see if the queue is empty or not
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+}
+
+impl<T> Default for Queue<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for Queue<T> {
+    // unlink nodes one at a time, dropping a long chain recursively can overflow the stack
+    fn drop(&mut self) {
+        for mut link in [self.front.take(), self.back.take()] {
+            while let Some(mut node) = link {
+                link = node.next.take();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pop_empty() {
+        let mut queue = Queue::<i32>::new();
+        assert!(queue.is_empty());
+        assert!(queue.pop().is_none());
+    }
+
+    #[test]
+    fn test_pop_in_push_order() {
+        let mut queue = Queue::new();
+        for i in 0..10 {
+            queue.push(i);
+        }
+        assert_eq!(queue.len(), 10);
+        for i in 0..10 {
+            assert_eq!(queue.pop(), Some(i));
+        }
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_interleaved_push_pop() {
+        let mut queue = Queue::new();
+        queue.push(1);
+        queue.push(2);
+        assert_eq!(queue.pop(), Some(1));
+        queue.push(3);
+        queue.push(4);
+        assert_eq!(queue.pop(), Some(2));
+        assert_eq!(queue.pop(), Some(3));
+        queue.push(5);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.pop(), Some(4));
+        assert_eq!(queue.pop(), Some(5));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_drop_long_queue() {
+        let mut queue = Queue::new();
+        for i in 0..1_000_000 {
+            queue.push(i);
+        }
+        queue.pop();
+        queue.push(0);
+    }
+}
@@ -63,6 +63,16 @@ impl<T> Stack<T> {
     }
 }
 
+impl<T> Drop for Stack<T> {
+    // unlink nodes one at a time, dropping a long chain recursively can overflow the stack
+    fn drop(&mut self) {
+        let mut link = self.top.take();
+        while let Some(mut node) = link {
+            link = node.next.take();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
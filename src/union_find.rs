@@ -0,0 +1,150 @@
+/* Disjoint set forest over the elements 0..len().
+
+Each set is a tree of parent links rooted at its representative. find
+compresses the path it walks so later lookups are shorter, and union hangs
+the shallower tree (by rank) under the deeper one, which together keep every
+operation close to constant time. */
+pub struct UnionFind {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+    size: Vec<usize>,
+    sets: usize
+}
+
+impl UnionFind {
+    /// Constructs size singleton sets, one for each element 0..size
+    pub fn new(size: usize) -> Self {
+        UnionFind {
+            parent: (0..size).collect(),
+            rank: vec![0; size],
+            size: vec![1; size],
+            sets: size
+        }
+    }
+
+    /// Adds a new element in a set of its own, returning the element
+    pub fn add(&mut self) -> usize {
+        let element = self.parent.len();
+        self.parent.push(element);
+        self.rank.push(0);
+        self.size.push(1);
+        self.sets += 1;
+        element
+    }
+
+    /// returns the number of elements
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    /// checks to see if there are no elements at all
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// returns the number of disjoint sets
+    pub fn set_count(&self) -> usize {
+        self.sets
+    }
+
+    /// Returns the representative of the set holding element
+    /// Panics if element is out of range
+    pub fn find(&mut self, element: usize) -> usize {
+        let mut root = element;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        // point everything on the walked path straight at the root
+        let mut cur = element;
+        while self.parent[cur] != root {
+            let next = self.parent[cur];
+            self.parent[cur] = root;
+            cur = next;
+        }
+        root
+    }
+
+    /// Merges the sets holding a and b
+    /// Returns false if they were already in the same set
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let root_a = self.find(a);
+        let root_b = self.find(b);
+        if root_a == root_b {
+            return false;
+        }
+        let (big, small) = if self.rank[root_a] >= self.rank[root_b] { (root_a, root_b) } else { (root_b, root_a) };
+        self.parent[small] = big;
+        self.size[big] += self.size[small];
+        if self.rank[big] == self.rank[small] {
+            self.rank[big] += 1;
+        }
+        self.sets -= 1;
+        true
+    }
+
+    /// checks to see if a and b are in the same set
+    pub fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// returns the number of elements in the set holding element
+    pub fn set_size(&mut self, element: usize) -> usize {
+        let root = self.find(element);
+        self.size[root]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_as_singletons() {
+        let mut sets = UnionFind::new(5);
+        assert_eq!(sets.len(), 5);
+        assert_eq!(sets.set_count(), 5);
+        for i in 0..5 {
+            assert_eq!(sets.find(i), i);
+            assert_eq!(sets.set_size(i), 1);
+        }
+        assert!(!sets.connected(0, 1));
+    }
+
+    #[test]
+    fn union_merges_sets() {
+        let mut sets = UnionFind::new(6);
+        assert!(sets.union(0, 1));
+        assert!(sets.union(2, 3));
+        assert!(sets.union(1, 3));
+        assert!(!sets.union(0, 2));
+        assert_eq!(sets.set_count(), 3);
+        assert!(sets.connected(0, 3));
+        assert!(!sets.connected(0, 4));
+        assert_eq!(sets.set_size(2), 4);
+        assert_eq!(sets.set_size(5), 1);
+    }
+
+    #[test]
+    fn add_elements() {
+        let mut sets = UnionFind::new(0);
+        assert!(sets.is_empty());
+        let a = sets.add();
+        let b = sets.add();
+        assert_eq!((a, b), (0, 1));
+        assert_eq!(sets.set_count(), 2);
+        sets.union(a, b);
+        assert_eq!(sets.set_count(), 1);
+    }
+
+    #[test]
+    fn long_chain_compresses() {
+        let mut sets = UnionFind::new(100_000);
+        for i in 1..100_000 {
+            sets.union(i - 1, i);
+        }
+        assert_eq!(sets.set_count(), 1);
+        let root = sets.find(99_999);
+        assert_eq!(sets.parent[99_999], root);
+        assert_eq!(sets.set_size(0), 100_000);
+    }
+}